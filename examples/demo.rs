@@ -1,5 +1,9 @@
-use shiden::{engines::price::PriceStateEngine, metrics::BotMetrics, run::run_bot};
-use tokio_util::sync::CancellationToken;
+use shiden::{
+    engines::price::PriceStateEngine,
+    metrics::BotMetrics,
+    models::{EventSource, InstrumentKind, InstrumentRegistry},
+    run::BotBuilder,
+};
 
 #[tokio::main]
 async fn main() {
@@ -8,40 +12,68 @@ async fn main() {
     let metrics_addr: std::net::SocketAddr = "0.0.0.0:9090".parse().unwrap();
     BotMetrics::init(metrics_addr);
 
+    // Map canonical instruments to venue-native symbols
+    let mut registry = InstrumentRegistry::new();
+    registry
+        .register(
+            EventSource::Binance,
+            "BTC/USDT",
+            InstrumentKind::Spot,
+            "btcusdt",
+        )
+        .unwrap();
+    registry
+        .register(
+            EventSource::Bybit,
+            "BTC/USDT",
+            InstrumentKind::Spot,
+            "BTCUSDT",
+        )
+        .unwrap();
+    registry
+        .register(
+            EventSource::Coinbase,
+            "BTC/USD",
+            InstrumentKind::Spot,
+            "BTC-USD",
+        )
+        .unwrap();
+
     // Initialize the Echo strategy
     let echo_strategy = shiden::strategies::echo::EchoStrategy;
-    let binance_collector = shiden::collectors::binance::BinanceCollector;
-    let bybit_collector = shiden::collectors::bybit::BybitCollector;
-    let coinbase_collector = shiden::collectors::coinbase::CoinbaseCollector;
+    let binance_collector = shiden::collectors::binance::BinanceCollector::new(
+        registry.instruments(&EventSource::Binance).cloned(),
+    );
+    let bybit_collector = shiden::collectors::bybit::BybitCollector::new(
+        registry.instruments(&EventSource::Bybit).cloned(),
+    );
+    let coinbase_collector = shiden::collectors::coinbase::CoinbaseCollector::new(
+        registry.instruments(&EventSource::Coinbase).cloned(),
+    );
     let echo_executor = shiden::executors::echo::EchoExecutor;
     let price_engine = PriceStateEngine::new(1_000); // 60 second candle timeframe
-    let shutdown = CancellationToken::new();
-
-    let mut set = run_bot(
-        echo_strategy,
-        vec![Box::new(price_engine)],
-        vec![
-            Box::new(binance_collector),
-            Box::new(bybit_collector),
-            Box::new(coinbase_collector),
-        ],
-        vec![Box::new(echo_executor)],
-        shutdown.clone(),
-    );
+    let mut bot = BotBuilder::new()
+        .with_strategy("echo", echo_strategy)
+        .with_state(price_engine)
+        .with_collector(binance_collector)
+        .with_collector(bybit_collector)
+        .with_collector(coinbase_collector)
+        .with_executor(echo_executor)
+        .start();
 
-    // Wait for shutdown signal
-    tokio::signal::ctrl_c()
-        .await
-        .expect("Failed to listen for shutdown signal");
-
-    tracing::info!("Shutdown signal received, stopping bot...");
-    shutdown.cancel();
-
-    // Wait for all tasks to complete
-    while let Some(result) = set.join_next().await {
-        match result {
-            Ok(_) => tracing::info!("Task completed successfully"),
-            Err(e) => tracing::error!("Task failed: {}", e),
+    // Run until interrupted or a component fails
+    tokio::select! {
+        result = tokio::signal::ctrl_c() => {
+            result.expect("Failed to listen for shutdown signal");
+            tracing::info!("Shutdown signal received, stopping bot...");
+        }
+        Some(failure) = bot.failure() => {
+            tracing::error!("{}, stopping bot...", failure);
         }
     }
+
+    bot.shutdown().await;
+    for (component, status) in bot.status() {
+        tracing::info!("{}: {:?}", component, status);
+    }
 }
@@ -0,0 +1,55 @@
+use std::sync::Arc;
+
+use shiden::{
+    collectors::replay::{ReplayCollector, ReplaySpeed},
+    engines::{book::OrderBookStateEngine, price::PriceStateEngine},
+    models::SimulatedClock,
+    run::BotBuilder,
+};
+
+/// Replays a directory of recordings through the demo setup without any network access
+///
+/// Usage: `cargo run --example replay -- <recordings dir> [speed multiple]`
+#[tokio::main]
+async fn main() {
+    // Initialize logging
+    tracing_subscriber::fmt::init();
+
+    let mut args = std::env::args().skip(1);
+    let dir = args.next().unwrap_or_else(|| "recordings".to_string());
+    let speed = match args.next() {
+        Some(multiple) => ReplaySpeed::Multiple(multiple.parse().expect("Invalid speed multiple")),
+        None => ReplaySpeed::RealTime,
+    };
+
+    let replay_collector = ReplayCollector::from_dir(&dir)
+        .expect("Failed to list recordings")
+        .with_speed(speed);
+    let echo_strategy = shiden::strategies::echo::EchoStrategy;
+    let echo_executor = shiden::executors::echo::EchoExecutor;
+    let price_engine = PriceStateEngine::new(1_000);
+    let book_engine = OrderBookStateEngine::new(vec![10.0, 50.0]);
+    let mut bot = BotBuilder::new()
+        .with_strategy("echo", echo_strategy)
+        .with_state(price_engine)
+        .with_state(book_engine)
+        .with_collector(replay_collector)
+        .with_executor(echo_executor)
+        // Strategy ticks follow the recorded event time rather than the replay speed
+        .with_clock(Arc::new(SimulatedClock::default()))
+        .start();
+
+    // Run until the recordings are exhausted, interrupted or a component fails
+    tokio::select! {
+        result = tokio::signal::ctrl_c() => {
+            result.expect("Failed to listen for shutdown signal");
+            tracing::info!("Shutdown signal received, stopping replay...");
+        }
+        failure = bot.failure() => match failure {
+            Some(failure) => tracing::error!("{}, stopping replay...", failure),
+            None => tracing::info!("Replay finished"),
+        }
+    }
+
+    bot.shutdown().await;
+}
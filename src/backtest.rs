@@ -0,0 +1,586 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use tokio::sync::broadcast::{self, error::TryRecvError};
+use tokio_stream::StreamExt as _;
+
+use crate::{
+    models::{
+        ActionFilter, Clock, Collector, EventPublisher, Executor, InputPolicy, OneShot,
+        RegisteredStrategy, Routable, SimulatedClock, StateEngine, Strategy, Timestamped,
+        TriggerEvent,
+    },
+    queue::{ActionReceiver, ActionSender, action_queue},
+    run::{execute, executor_filter, route},
+    schedule::EvaluationSchedule,
+};
+
+/// Counters describing a completed backtest
+#[derive(Debug, Default, Clone)]
+pub struct BacktestSummary {
+    pub events: usize,
+    pub evaluations: usize,
+    pub actions: usize,
+    /// Timestamp of the first and last timestamped event replayed
+    pub start: Option<u64>,
+    pub end: Option<u64>,
+}
+
+/// Runs a strategy against historical events through the same components used by a live bot.
+///
+/// Unlike a live bot, everything runs sequentially on the calling task so results are
+/// deterministic:
+/// 1. Collector streams are merged into a single stream ordered by event timestamp
+/// 2. Before each event, the strategy is evaluated for every interval elapsed on the simulated
+///    clock, which starts at the first event and advances with event timestamps, and for
+///    triggers fired by earlier events once their debounce has passed
+/// 3. Each evaluation requests data from all State Engines. Actions are tagged with
+///    `strategy_id` and routed like in a live bot, to the Executors whose entry in `routes`, or
+///    else whose own filter, accepts them, through queues applying each Executor's policy. Once
+///    the evaluation is routed, or when a blocking queue is full, queued actions are executed in
+///    order, Executor by Executor
+/// 4. Events published by Executors are delivered to State Engines right after the action or
+///    event that produced them, and fire triggers like collected events
+/// 5. The backtest finishes once every collector stream is exhausted
+///
+/// Events without a timestamp are delivered as soon as they reach the head of their stream.
+///
+/// `clock` should be the clock given to any component that needs the current time. It is set
+/// to each event's timestamp before the event is delivered and to the evaluation time before
+/// each evaluation. As nothing else advances it while a component sleeps, sleeping on it
+/// advances it immediately.
+pub async fn run_backtest<S, E, D, I, A>(
+    strategy_id: &str,
+    strategy: S,
+    mut states: Vec<Box<dyn StateEngine<E, D>>>,
+    collectors: Vec<Box<dyn Collector<E>>>,
+    executors: Vec<Box<dyn Executor<A, E>>>,
+    routes: &HashMap<String, ActionFilter>,
+    clock: Arc<SimulatedClock>,
+) -> anyhow::Result<BacktestSummary>
+where
+    S: Strategy<D, I, A> + 'static,
+    E: Timestamped + TriggerEvent + Clone + Send + Sync + 'static,
+    D: 'static,
+    I: 'static,
+    A: Routable + Clone + Send + Sync + 'static,
+{
+    clock.set_auto_advance(true);
+
+    let mut schedule = EvaluationSchedule::for_strategy(&strategy);
+    let strategy = RegisteredStrategy::new(strategy_id, strategy);
+
+    let (feedback_tx, mut feedback_rx) = broadcast::channel::<E>(1024);
+    let mut router = Router {
+        action_txs: Vec::with_capacity(executors.len()),
+        executors: Vec::with_capacity(executors.len()),
+        action_rxs: Vec::with_capacity(executors.len()),
+    };
+    for mut executor in executors {
+        executor.set_event_publisher(EventPublisher::new(feedback_tx.clone()));
+
+        let executor: Arc<dyn Executor<A, E>> = Arc::from(executor);
+        let (action_tx, action_rx) = action_queue(executor.clone());
+        router
+            .action_txs
+            .push((executor_filter(routes, executor.as_ref()), action_tx));
+        router.executors.push(executor);
+        router.action_rxs.push(action_rx);
+    }
+
+    for state in &mut states {
+        state.sync_state().await?;
+    }
+
+    let mut streams = Vec::new();
+    for collector in &collectors {
+        streams.push(collector.get_event_stream().await?);
+    }
+    let mut heads = Vec::with_capacity(streams.len());
+    for stream in &mut streams {
+        heads.push(stream.next().await);
+    }
+
+    let mut summary = BacktestSummary::default();
+
+    while let Some(index) = next_stream(&heads) {
+        let Some(event) = heads[index].take() else {
+            break;
+        };
+        heads[index] = streams[index].next().await;
+
+        if let Some(timestamp) = event.timestamp() {
+            schedule.start_at(timestamp + strategy.interval_ms());
+            while let Some(due) = schedule.next_due().filter(|due| *due <= timestamp) {
+                clock.advance_to(due);
+                schedule.evaluated(clock.now_ms());
+                summary.actions += evaluate(
+                    &strategy,
+                    &mut states,
+                    &router,
+                    &mut feedback_rx,
+                    &mut schedule,
+                )
+                .await?;
+                summary.evaluations += 1;
+            }
+
+            summary.start.get_or_insert(timestamp);
+            summary.end = Some(timestamp);
+            clock.advance_to(timestamp);
+        }
+
+        schedule.observe(&event);
+        dispatch(&mut states, event);
+        drain_feedback(&mut states, &mut feedback_rx, &mut schedule);
+        summary.events += 1;
+    }
+
+    // A trigger fired by the last events is still evaluated, once its debounce has passed
+    if let Some(due) = schedule.next_due().filter(|_| schedule.is_pending()) {
+        clock.advance_to(due);
+        schedule.evaluated(clock.now_ms());
+        summary.actions += evaluate(
+            &strategy,
+            &mut states,
+            &router,
+            &mut feedback_rx,
+            &mut schedule,
+        )
+        .await?;
+        summary.evaluations += 1;
+    }
+
+    for state in &mut states {
+        if let Err(e) = state.on_shutdown() {
+            tracing::error!("Error during shutdown of state {}: {}", state.name(), e);
+        }
+    }
+
+    tracing::info!("Backtest finished: {:?}", summary);
+    Ok(summary)
+}
+
+/// Index of the stream whose head event comes next, untimestamped events first and ties broken
+/// by stream order
+fn next_stream<E: Timestamped>(heads: &[Option<E>]) -> Option<usize> {
+    heads
+        .iter()
+        .enumerate()
+        .filter_map(|(index, head)| head.as_ref().map(|event| (index, event.timestamp())))
+        .min_by_key(|(index, timestamp)| (timestamp.unwrap_or(0), *index))
+        .map(|(index, _)| index)
+}
+
+fn dispatch<E: Clone, D>(states: &mut [Box<dyn StateEngine<E, D>>], event: E) {
+    for state in states.iter_mut() {
+        if let Err(e) = state.process_event(event.clone()) {
+            tracing::error!("Error processing event in state {}: {}", state.name(), e);
+        }
+    }
+}
+
+/// Delivers the events executors published, which can fire triggers like collected events do
+fn drain_feedback<E: TriggerEvent + Clone, D>(
+    states: &mut [Box<dyn StateEngine<E, D>>],
+    feedback_rx: &mut broadcast::Receiver<E>,
+    schedule: &mut EvaluationSchedule,
+) {
+    loop {
+        match feedback_rx.try_recv() {
+            Ok(event) => {
+                schedule.observe(&event);
+                dispatch(states, event);
+            }
+            Err(TryRecvError::Lagged(skipped)) => {
+                tracing::warn!("Backtest feedback lagged, {} events skipped", skipped);
+            }
+            Err(TryRecvError::Empty) | Err(TryRecvError::Closed) => break,
+        }
+    }
+}
+
+/// Executor queues of a backtest, in the order the executors were given
+struct Router<A, E> {
+    action_txs: Vec<(ActionFilter, ActionSender<A, E>)>,
+    executors: Vec<Arc<dyn Executor<A, E>>>,
+    action_rxs: Vec<ActionReceiver<A>>,
+}
+
+impl<A, E> Router<A, E>
+where
+    A: Routable + Clone,
+    E: TriggerEvent + Clone,
+{
+    /// Queues an action for every executor accepting it, running the queued actions first when
+    /// a blocking queue is full
+    async fn push<D>(
+        &self,
+        strategy_id: &str,
+        action: A,
+        states: &mut [Box<dyn StateEngine<E, D>>],
+        feedback_rx: &mut broadcast::Receiver<E>,
+        schedule: &mut EvaluationSchedule,
+    ) {
+        for (action_tx, action) in route(strategy_id, action, &self.action_txs) {
+            if let Err(action) = action_tx.try_push(action) {
+                self.execute_queued(states, feedback_rx, schedule).await;
+                if let Err(action) = action_tx.try_push(action) {
+                    tracing::warn!(
+                        "Executor {} cannot take actions, dropped action from strategy {}",
+                        action_tx.name(),
+                        strategy_id
+                    );
+                    action_tx.discard(&action);
+                }
+            }
+        }
+    }
+
+    /// Executes every queued action, delivering the events each one published before the next
+    async fn execute_queued<D>(
+        &self,
+        states: &mut [Box<dyn StateEngine<E, D>>],
+        feedback_rx: &mut broadcast::Receiver<E>,
+        schedule: &mut EvaluationSchedule,
+    ) {
+        // Actions run one at a time, so none is ever in flight
+        let in_flight = HashSet::new();
+        for (executor, action_rx) in self.executors.iter().zip(&self.action_rxs) {
+            while let Some(queued) = action_rx.pop_ready(&in_flight) {
+                execute(executor.clone(), queued).await;
+                drain_feedback(states, feedback_rx, schedule);
+            }
+        }
+    }
+}
+
+/// Runs one strategy evaluation, returning the number of actions the strategy emitted
+async fn evaluate<E, D, A>(
+    strategy: &RegisteredStrategy<D, A>,
+    states: &mut [Box<dyn StateEngine<E, D>>],
+    router: &Router<A, E>,
+    feedback_rx: &mut broadcast::Receiver<E>,
+    schedule: &mut EvaluationSchedule,
+) -> anyhow::Result<usize>
+where
+    E: TriggerEvent + Clone,
+    A: Routable + Clone,
+{
+    let mut data = Vec::with_capacity(states.len());
+    let mut missing = Vec::new();
+    for state in states.iter() {
+        let (request, mut rx) = OneShot::new();
+        match state
+            .process_request(request)
+            .and_then(|_| Ok(rx.try_recv()?))
+        {
+            Ok(response) => data.push(response),
+            Err(e) => {
+                tracing::warn!(
+                    "State {} failed to respond for strategy {}: {}",
+                    state.name(),
+                    strategy.id(),
+                    e
+                );
+                missing.push(state.name());
+            }
+        }
+    }
+
+    if !missing.is_empty() && strategy.input_policy() == InputPolicy::Complete {
+        tracing::debug!(
+            "Missing input from {} for strategy {}, skipping",
+            missing.join(", "),
+            strategy.id()
+        );
+        return Ok(0);
+    }
+
+    let actions = match strategy.evaluate(data, &missing) {
+        Ok(actions) => actions,
+        Err(e) => {
+            tracing::debug!("Error building input: {}, skipping", e);
+            return Ok(0);
+        }
+    };
+
+    let emitted = actions.len();
+    for action in actions {
+        router
+            .push(strategy.id(), action, states, feedback_rx, schedule)
+            .await;
+    }
+    router.execute_queued(states, feedback_rx, schedule).await;
+
+    Ok(emitted)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::{
+        models::{
+            Action, CancelRequest, CollectorError, CollectorStream, EventSource, InputBuilder,
+            InternalEvent, StateOutput, Trigger, order::Side,
+        },
+        test_support as support,
+    };
+
+    /// Replays a fixed list of events
+    struct Events(Vec<InternalEvent>);
+
+    #[async_trait::async_trait]
+    impl Collector<InternalEvent> for Events {
+        fn name(&self) -> &'static str {
+            "events"
+        }
+
+        async fn get_event_stream(
+            &self,
+        ) -> Result<CollectorStream<'_, InternalEvent>, CollectorError> {
+            Ok(Box::pin(tokio_stream::iter(self.0.clone())))
+        }
+    }
+
+    fn trades(timestamps: &[u64]) -> Events {
+        Events(
+            timestamps
+                .iter()
+                .map(|&at| InternalEvent::Trade(support::trade(EventSource::Binance, 1.0, 1.0, at)))
+                .collect(),
+        )
+    }
+
+    /// Records the timestamp of every event it is delivered
+    struct Seen(Arc<Mutex<Vec<Option<u64>>>>);
+
+    #[async_trait::async_trait]
+    impl StateEngine<InternalEvent, StateOutput> for Seen {
+        fn name(&self) -> &'static str {
+            "seen"
+        }
+
+        async fn sync_state(&mut self) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        fn process_event(&mut self, event: InternalEvent) -> anyhow::Result<()> {
+            self.0.lock().unwrap().push(event.timestamp());
+            Ok(())
+        }
+
+        fn process_request(&self, request: OneShot<StateOutput>) -> anyhow::Result<()> {
+            request.respond(StateOutput::Prices(Vec::new()))
+        }
+
+        fn on_shutdown(&mut self) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct NoInput;
+
+    impl InputBuilder<StateOutput, ()> for NoInput {
+        fn insert(&mut self, _data: StateOutput) {}
+
+        fn build(self) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Records the time of each evaluation, emitting a cancel on the first one
+    struct Probe {
+        interval_ms: u64,
+        triggers: Vec<Trigger>,
+        debounce_ms: u64,
+        clock: Arc<SimulatedClock>,
+        evaluations: Arc<Mutex<Vec<u64>>>,
+    }
+
+    impl Strategy<StateOutput, (), Action> for Probe {
+        type InputBuilder = NoInput;
+
+        fn name(&self) -> &'static str {
+            "probe"
+        }
+
+        fn interval_ms(&self) -> u64 {
+            self.interval_ms
+        }
+
+        fn triggers(&self) -> Vec<Trigger> {
+            self.triggers.clone()
+        }
+
+        fn debounce_ms(&self) -> u64 {
+            self.debounce_ms
+        }
+
+        fn evaluate(&self, _input: ()) -> Vec<Action> {
+            let mut evaluations = self.evaluations.lock().unwrap();
+            evaluations.push(self.clock.now_ms());
+            if evaluations.len() > 1 {
+                return Vec::new();
+            }
+            vec![Action::Cancel(CancelRequest {
+                client_id: "1".to_string(),
+                source: EventSource::Binance,
+                instrument: support::instrument(),
+                strategy_id: None,
+            })]
+        }
+    }
+
+    /// Reports a fill for every action it executes
+    #[derive(Default)]
+    struct Filling(Mutex<Option<EventPublisher<InternalEvent>>>);
+
+    #[async_trait::async_trait]
+    impl Executor<Action, InternalEvent> for Filling {
+        fn name(&self) -> &'static str {
+            "filling"
+        }
+
+        async fn execute(&self, action: Action) -> anyhow::Result<()> {
+            let fill = support::fill(action.client_id(), Side::Buy, 1.0, 1.0, 0.0, 0);
+            let publisher = self.0.lock().unwrap().clone();
+            publisher
+                .expect("Executor started without a publisher")
+                .publish(InternalEvent::ExecutionReport(support::fill_report(fill)))
+        }
+
+        fn set_event_publisher(&mut self, publisher: EventPublisher<InternalEvent>) {
+            *self.0.lock().unwrap() = Some(publisher);
+        }
+    }
+
+    struct Run {
+        seen: Vec<Option<u64>>,
+        evaluations: Vec<u64>,
+        summary: BacktestSummary,
+    }
+
+    async fn backtest(
+        interval_ms: u64,
+        triggers: Vec<Trigger>,
+        debounce_ms: u64,
+        collectors: Vec<Events>,
+    ) -> Run {
+        let clock = Arc::new(SimulatedClock::default());
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let evaluations = Arc::new(Mutex::new(Vec::new()));
+        let strategy = Probe {
+            interval_ms,
+            triggers,
+            debounce_ms,
+            clock: clock.clone(),
+            evaluations: evaluations.clone(),
+        };
+
+        let summary = run_backtest(
+            "probe",
+            strategy,
+            vec![Box::new(Seen(seen.clone()))],
+            collectors
+                .into_iter()
+                .map(|collector| Box::new(collector) as Box<dyn Collector<InternalEvent>>)
+                .collect(),
+            vec![Box::new(Filling::default())],
+            &HashMap::new(),
+            clock,
+        )
+        .await
+        .unwrap();
+
+        let seen = seen.lock().unwrap().clone();
+        let evaluations = evaluations.lock().unwrap().clone();
+        Run {
+            seen,
+            evaluations,
+            summary,
+        }
+    }
+
+    #[tokio::test]
+    async fn streams_are_merged_in_timestamp_order() {
+        let untimestamped = Events(vec![InternalEvent::Resync(EventSource::Bybit)]);
+
+        let run = backtest(
+            0,
+            Vec::new(),
+            0,
+            vec![trades(&[1, 4, 4]), trades(&[2, 4, 6]), untimestamped],
+        )
+        .await;
+
+        assert_eq!(
+            run.seen,
+            vec![None, Some(1), Some(2), Some(4), Some(4), Some(4), Some(6)]
+        );
+        assert_eq!((run.summary.start, run.summary.end), (Some(1), Some(6)));
+        assert_eq!(run.summary.events, 7);
+    }
+
+    #[test]
+    fn ties_go_to_the_earlier_stream() {
+        let heads = [
+            Some(InternalEvent::Trade(support::trade(
+                EventSource::Binance,
+                1.0,
+                1.0,
+                4,
+            ))),
+            None,
+            Some(InternalEvent::Trade(support::trade(
+                EventSource::Bybit,
+                1.0,
+                1.0,
+                4,
+            ))),
+        ];
+
+        assert_eq!(next_stream(&heads), Some(0));
+        assert_eq!(next_stream::<InternalEvent>(&[None, None]), None);
+    }
+
+    #[tokio::test]
+    async fn elapsed_intervals_are_evaluated_before_the_next_event() {
+        let run = backtest(1_000, Vec::new(), 0, vec![trades(&[0, 3_500, 4_000])]).await;
+
+        assert_eq!(run.evaluations, vec![1_000, 2_000, 3_000, 4_000]);
+        assert_eq!(run.summary.evaluations, 4);
+    }
+
+    #[tokio::test]
+    async fn a_trigger_pending_at_the_end_is_evaluated() {
+        let run = backtest(
+            0,
+            vec![Trigger::EveryEvents(1)],
+            500,
+            vec![trades(&[0, 100])],
+        )
+        .await;
+
+        assert_eq!(run.evaluations, vec![0, 500]);
+    }
+
+    #[tokio::test]
+    async fn executor_feedback_fires_triggers() {
+        let triggers = vec![Trigger::EventType {
+            event_type: "ExecutionReport".to_string(),
+            source: None,
+        }];
+
+        let run = backtest(1_000, triggers, 100, vec![trades(&[0, 1_050, 2_500])]).await;
+
+        // The fill of the action emitted at 1000 is evaluated once the debounce passed
+        assert_eq!(run.evaluations, vec![1_000, 1_100, 2_000]);
+        assert_eq!(run.summary.actions, 1);
+    }
+}
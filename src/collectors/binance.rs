@@ -1,9 +1,84 @@
+use std::sync::Arc;
+
 use exstreamer::{error::ExStreamError, models::BinanceMessage};
-use futures::StreamExt;
+use futures::{StreamExt, stream};
+
+use super::decode_error;
+use crate::models::{
+    BinanceDepthSnapshot, BookUpdate, Collector, CollectorError, CollectorStream, ConnectionStatus,
+    EventSource, Instrument, InstrumentKind, InstrumentRegistry, InternalEvent, LiveClock,
+    SharedClock, normalize_symbol,
+};
+
+/// REST endpoint serving the book snapshots the diff-depth stream is applied on
+const SNAPSHOT_URL: &str = "https://api.binance.com/api/v3/depth";
+/// Levels per side requested in book snapshots
+const SNAPSHOT_LIMIT: u32 = 1_000;
+
+/// Market category of the public Binance streams, symbols are resolved within it
+const KIND: InstrumentKind = InstrumentKind::Spot;
+
+pub struct BinanceCollector {
+    registry: InstrumentRegistry,
+    depth: bool,
+    client: reqwest::Client,
+    clock: SharedClock,
+}
 
-use crate::models::{Collector, CollectorStream, InternalEvent};
+impl BinanceCollector {
+    /// Creates a collector subscribing to trades for each instrument's venue-native symbol,
+    /// instruments of other market categories than the stream serves are skipped
+    pub fn new(instruments: impl IntoIterator<Item = Instrument>) -> Self {
+        let mut registry = InstrumentRegistry::new();
+        for instrument in instruments {
+            if instrument.kind != KIND {
+                tracing::warn!(
+                    "Binance collector streams {:?} markets, skipping {} {:?}",
+                    KIND,
+                    instrument,
+                    instrument.kind
+                );
+                continue;
+            }
+            registry.insert(EventSource::Binance, instrument);
+        }
+        Self {
+            registry,
+            depth: false,
+            client: reqwest::Client::new(),
+            clock: Arc::new(LiveClock),
+        }
+    }
+
+    /// Also subscribes to the order book channel of each instrument
+    pub fn with_depth(mut self) -> Self {
+        self.depth = true;
+        self
+    }
+
+    /// Stamps book snapshots, which carry no time of their own, with `clock`
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
 
-pub struct BinanceCollector;
+    async fn fetch_snapshot(&self, instrument: &Instrument) -> anyhow::Result<BookUpdate> {
+        let snapshot = self
+            .client
+            .get(SNAPSHOT_URL)
+            .query(&[
+                ("symbol", normalize_symbol(&instrument.symbol)),
+                ("limit", SNAPSHOT_LIMIT.to_string()),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<BinanceDepthSnapshot>()
+            .await?;
+
+        (snapshot, self.clock.now_ms(), instrument.clone()).try_into()
+    }
+}
 
 #[async_trait::async_trait]
 impl Collector<InternalEvent> for BinanceCollector {
@@ -11,28 +86,73 @@ impl Collector<InternalEvent> for BinanceCollector {
         "binance_collector"
     }
 
-    async fn get_event_stream(&self) -> anyhow::Result<CollectorStream<'_, InternalEvent>> {
-        let (stream, _) = exstreamer::StreamBuilder::binance()
-            .with_trade("btcusdt")
+    async fn get_event_stream(&self) -> Result<CollectorStream<'_, InternalEvent>, CollectorError> {
+        let mut instruments = self.registry.instruments(&EventSource::Binance).peekable();
+        if instruments.peek().is_none() {
+            return Err(CollectorError::SubscriptionRejected(
+                "Binance: no instruments configured".to_string(),
+            ));
+        }
+
+        let builder = instruments.fold(
+            exstreamer::StreamBuilder::binance(),
+            |builder, instrument| {
+                let builder = builder.with_trade(&instrument.symbol);
+                if self.depth {
+                    builder.with_depth(&instrument.symbol)
+                } else {
+                    builder
+                }
+            },
+        );
+        let (stream, _) = builder
             .connect()
             .await
-            .expect("Failed to create Binance streamer");
+            .map_err(|e| CollectorError::Connect(format!("Binance: {}", e)))?;
+
+        // The stream buffers depth changes from the subscription on, so snapshots fetched after
+        // connecting are followed by every change they do not cover yet
+        let mut snapshots = Vec::new();
+        if self.depth {
+            for instrument in self.registry.instruments(&EventSource::Binance) {
+                let snapshot = self.fetch_snapshot(instrument).await.map_err(|e| {
+                    CollectorError::Connect(format!("Binance: {} book snapshot: {}", instrument, e))
+                })?;
+                snapshots.push(InternalEvent::BookSnapshot(snapshot));
+            }
+        }
 
-        let internal_stream = stream.map(binance_result_to_internal_event);
+        let registry = &self.registry;
+        let internal_stream = stream::iter(snapshots)
+            .chain(stream.map(move |result| binance_result_to_internal_event(result, registry)));
 
         Ok(Box::pin(internal_stream))
     }
+
+    fn connection_event(&self, status: ConnectionStatus) -> Option<InternalEvent> {
+        Some(InternalEvent::Connection(EventSource::Binance, status))
+    }
+
+    fn resync_requested(&self, request: &InternalEvent) -> bool {
+        matches!(request, InternalEvent::Resync(EventSource::Binance))
+    }
 }
 
 fn binance_result_to_internal_event(
     result: Result<BinanceMessage, ExStreamError>,
+    registry: &InstrumentRegistry,
 ) -> InternalEvent {
     match result {
-        Ok(BinanceMessage::Trade(trade)) => match trade.try_into() {
+        Ok(BinanceMessage::Trade(trade)) => match (trade, KIND, registry).try_into() {
             Ok(trade) => InternalEvent::Trade(trade),
-            Err(e) => InternalEvent::Error(e.to_string()),
+            Err(e) => decode_error("Binance", e),
+        },
+        // Diff-depth messages only carry the levels changed since the previous one
+        Ok(BinanceMessage::Depth(depth)) => match (depth, KIND, registry).try_into() {
+            Ok(book) => InternalEvent::BookDelta(book),
+            Err(e) => decode_error("Binance", e),
         },
-        Err(e) => InternalEvent::Error(e.to_string()),
+        Err(e) => decode_error("Binance", e),
         _ => InternalEvent::Unsupported("Not supported".to_string()),
     }
 }
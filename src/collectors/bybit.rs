@@ -3,9 +3,49 @@ use std::pin::Pin;
 use exstreamer::{error::ExStreamError, models::BybitMessage};
 use futures::{Stream, StreamExt, stream};
 
-use crate::models::{Collector, CollectorStream, InternalEvent};
+use super::decode_error;
+use crate::models::{
+    Collector, CollectorError, CollectorStream, ConnectionStatus, EventSource, Instrument,
+    InstrumentKind, InstrumentRegistry, InternalEvent,
+};
 
-pub struct BybitCollector;
+/// Market category of the public Bybit streams, symbols are resolved within it
+const KIND: InstrumentKind = InstrumentKind::Spot;
+
+pub struct BybitCollector {
+    registry: InstrumentRegistry,
+    depth: bool,
+}
+
+impl BybitCollector {
+    /// Creates a collector subscribing to trades for each instrument's venue-native symbol,
+    /// instruments of other market categories than the stream serves are skipped
+    pub fn new(instruments: impl IntoIterator<Item = Instrument>) -> Self {
+        let mut registry = InstrumentRegistry::new();
+        for instrument in instruments {
+            if instrument.kind != KIND {
+                tracing::warn!(
+                    "Bybit collector streams {:?} markets, skipping {} {:?}",
+                    KIND,
+                    instrument,
+                    instrument.kind
+                );
+                continue;
+            }
+            registry.insert(EventSource::Bybit, instrument);
+        }
+        Self {
+            registry,
+            depth: false,
+        }
+    }
+
+    /// Also subscribes to the order book channel of each instrument
+    pub fn with_depth(mut self) -> Self {
+        self.depth = true;
+        self
+    }
+}
 
 #[async_trait::async_trait]
 impl Collector<InternalEvent> for BybitCollector {
@@ -13,32 +53,70 @@ impl Collector<InternalEvent> for BybitCollector {
         "bybit_collector"
     }
 
-    async fn get_event_stream(&self) -> anyhow::Result<CollectorStream<'_, InternalEvent>> {
-        let (stream, _) = exstreamer::StreamBuilder::bybit()
-            .with_trade("btcusdt")
+    async fn get_event_stream(&self) -> Result<CollectorStream<'_, InternalEvent>, CollectorError> {
+        let mut instruments = self.registry.instruments(&EventSource::Bybit).peekable();
+        if instruments.peek().is_none() {
+            return Err(CollectorError::SubscriptionRejected(
+                "Bybit: no instruments configured".to_string(),
+            ));
+        }
+
+        let builder =
+            instruments.fold(exstreamer::StreamBuilder::bybit(), |builder, instrument| {
+                let builder = builder.with_trade(&instrument.symbol);
+                if self.depth {
+                    builder.with_depth(&instrument.symbol)
+                } else {
+                    builder
+                }
+            });
+        let (stream, _) = builder
             .connect()
             .await
-            .expect("Failed to create Bybit streamer");
+            .map_err(|e| CollectorError::Connect(format!("Bybit: {}", e)))?;
 
-        let internal_stream = stream.flat_map(bybit_result_to_internal_event);
+        let registry = &self.registry;
+        let internal_stream =
+            stream.flat_map(move |result| bybit_result_to_internal_event(result, registry));
 
         Ok(Box::pin(internal_stream))
     }
+
+    fn connection_event(&self, status: ConnectionStatus) -> Option<InternalEvent> {
+        Some(InternalEvent::Connection(EventSource::Bybit, status))
+    }
+
+    fn resync_requested(&self, request: &InternalEvent) -> bool {
+        matches!(request, InternalEvent::Resync(EventSource::Bybit))
+    }
 }
 
 fn bybit_result_to_internal_event(
     result: Result<BybitMessage, ExStreamError>,
+    registry: &InstrumentRegistry,
 ) -> Pin<Box<dyn Stream<Item = InternalEvent> + Send>> {
     match result {
-        Ok(BybitMessage::Trade(trades)) => Box::pin(stream::iter(trades.data.into_iter().map(
-            |trade| match trade.try_into() {
-                Ok(trade) => InternalEvent::Trade(trade),
-                Err(e) => InternalEvent::Error(e.to_string()),
-            },
-        ))),
-        Err(e) => Box::pin(stream::once(
-            async move { InternalEvent::Error(e.to_string()) },
-        )),
+        Ok(BybitMessage::Trade(trades)) => {
+            let events = trades
+                .data
+                .into_iter()
+                .map(|trade| match (trade, KIND, registry).try_into() {
+                    Ok(trade) => InternalEvent::Trade(trade),
+                    Err(e) => decode_error("Bybit", e),
+                })
+                .collect::<Vec<_>>();
+            Box::pin(stream::iter(events))
+        }
+        Ok(BybitMessage::OrderBook(book)) => {
+            let is_snapshot = book.message_type == "snapshot";
+            let event = match (book.data, book.timestamp, KIND, registry).try_into() {
+                Ok(book) if is_snapshot => InternalEvent::BookSnapshot(book),
+                Ok(book) => InternalEvent::BookDelta(book),
+                Err(e) => decode_error("Bybit", e),
+            };
+            Box::pin(stream::once(async move { event }))
+        }
+        Err(e) => Box::pin(stream::once(async move { decode_error("Bybit", e) })),
         _ => Box::pin(stream::once(async {
             InternalEvent::Unsupported("Not supported".to_string())
         })),
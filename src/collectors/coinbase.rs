@@ -1,9 +1,59 @@
+use std::sync::Arc;
+
 use exstreamer::{error::ExStreamError, models::CoinbaseMessage};
 use futures::StreamExt;
 
-use crate::models::{Collector, CollectorStream, InternalEvent};
+use super::decode_error;
+use crate::models::{
+    Collector, CollectorError, CollectorStream, ConnectionStatus, EventSource, Instrument,
+    InstrumentKind, InstrumentRegistry, InternalEvent, LiveClock, SharedClock,
+};
+
+/// Market category of the public Coinbase streams, symbols are resolved within it
+const KIND: InstrumentKind = InstrumentKind::Spot;
+
+pub struct CoinbaseCollector {
+    registry: InstrumentRegistry,
+    depth: bool,
+    clock: SharedClock,
+}
+
+impl CoinbaseCollector {
+    /// Creates a collector subscribing to trades for each instrument's venue-native symbol,
+    /// instruments of other market categories than the stream serves are skipped
+    pub fn new(instruments: impl IntoIterator<Item = Instrument>) -> Self {
+        let mut registry = InstrumentRegistry::new();
+        for instrument in instruments {
+            if instrument.kind != KIND {
+                tracing::warn!(
+                    "Coinbase collector streams {:?} markets, skipping {} {:?}",
+                    KIND,
+                    instrument,
+                    instrument.kind
+                );
+                continue;
+            }
+            registry.insert(EventSource::Coinbase, instrument);
+        }
+        Self {
+            registry,
+            depth: false,
+            clock: Arc::new(LiveClock),
+        }
+    }
+
+    /// Also subscribes to the order book channel of each instrument
+    pub fn with_depth(mut self) -> Self {
+        self.depth = true;
+        self
+    }
 
-pub struct CoinbaseCollector;
+    /// Stamps book snapshots, which carry no time of their own, with `clock`
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+}
 
 #[async_trait::async_trait]
 impl Collector<InternalEvent> for CoinbaseCollector {
@@ -11,28 +61,68 @@ impl Collector<InternalEvent> for CoinbaseCollector {
         "coinbase_collector"
     }
 
-    async fn get_event_stream(&self) -> anyhow::Result<CollectorStream<'_, InternalEvent>> {
-        let (stream, _) = exstreamer::StreamBuilder::coinbase()
-            .with_trade("BTC-USD")
+    async fn get_event_stream(&self) -> Result<CollectorStream<'_, InternalEvent>, CollectorError> {
+        let mut instruments = self.registry.instruments(&EventSource::Coinbase).peekable();
+        if instruments.peek().is_none() {
+            return Err(CollectorError::SubscriptionRejected(
+                "Coinbase: no instruments configured".to_string(),
+            ));
+        }
+
+        let builder = instruments.fold(
+            exstreamer::StreamBuilder::coinbase(),
+            |builder, instrument| {
+                let builder = builder.with_trade(&instrument.symbol);
+                if self.depth {
+                    builder.with_depth(&instrument.symbol)
+                } else {
+                    builder
+                }
+            },
+        );
+        let (stream, _) = builder
             .connect()
             .await
-            .expect("Failed to create Coinbase streamer");
+            .map_err(|e| CollectorError::Connect(format!("Coinbase: {}", e)))?;
 
-        let internal_stream = stream.map(coinbase_result_to_internal_event);
+        let registry = &self.registry;
+        let clock = &self.clock;
+        let internal_stream = stream
+            .map(move |result| coinbase_result_to_internal_event(result, clock.now_ms(), registry));
 
         Ok(Box::pin(internal_stream))
     }
+
+    fn connection_event(&self, status: ConnectionStatus) -> Option<InternalEvent> {
+        Some(InternalEvent::Connection(EventSource::Coinbase, status))
+    }
+
+    fn resync_requested(&self, request: &InternalEvent) -> bool {
+        matches!(request, InternalEvent::Resync(EventSource::Coinbase))
+    }
 }
 
 fn coinbase_result_to_internal_event(
     result: Result<CoinbaseMessage, ExStreamError>,
+    received_at: u64,
+    registry: &InstrumentRegistry,
 ) -> InternalEvent {
     match result {
-        Ok(CoinbaseMessage::Ticker(tick)) => match (*tick).try_into() {
+        Ok(CoinbaseMessage::Ticker(tick)) => match (*tick, KIND, registry).try_into() {
             Ok(trade) => InternalEvent::Trade(trade),
-            Err(e) => InternalEvent::Error(e.to_string()),
+            Err(e) => decode_error("Coinbase", e),
+        },
+        Ok(CoinbaseMessage::Snapshot(snapshot)) => {
+            match (*snapshot, received_at, KIND, registry).try_into() {
+                Ok(book) => InternalEvent::BookSnapshot(book),
+                Err(e) => decode_error("Coinbase", e),
+            }
+        }
+        Ok(CoinbaseMessage::L2Update(update)) => match (*update, KIND, registry).try_into() {
+            Ok(book) => InternalEvent::BookDelta(book),
+            Err(e) => decode_error("Coinbase", e),
         },
-        Err(e) => InternalEvent::Error(e.to_string()),
+        Err(e) => decode_error("Coinbase", e),
         _ => InternalEvent::Unsupported("Not supported".to_string()),
     }
 }
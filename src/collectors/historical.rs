@@ -0,0 +1,559 @@
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use flate2::read::{DeflateDecoder, MultiGzDecoder};
+use tokio::sync::mpsc;
+use tokio_stream::StreamExt as _;
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::models::{
+    Collector, CollectorError, CollectorStream, EventSource, Instrument, InstrumentKind,
+    InstrumentRegistry, InternalEvent, ReconnectPolicy, Trade, parse_iso8601_to_timestamp,
+};
+
+/// Number of merged trades buffered ahead of the stream
+const READ_AHEAD: usize = 4_096;
+
+/// Signature starting the local header of each zip archive entry
+const ZIP_LOCAL_HEADER: [u8; 4] = *b"PK\x03\x04";
+
+type TradeIter = Box<dyn Iterator<Item = Trade> + Send>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TradeFileFormat {
+    /// data.binance.vision `trades` CSV: `id,price,qty,quote_qty,time,is_buyer_maker,...`
+    BinanceTrades,
+    /// data.binance.vision `aggTrades` CSV:
+    /// `agg_trade_id,price,quantity,first_trade_id,last_trade_id,transact_time,...`
+    BinanceAggTrades,
+    /// Coinbase trade export CSV with a header naming the `price`, `size` and `time` columns
+    CoinbaseTrades,
+    /// Parquet file with `price`, `size` (or `qty`/`quantity`) and `time` (or `timestamp`)
+    /// columns
+    #[cfg(feature = "parquet")]
+    Parquet,
+}
+
+/// A historical trade dump for a single instrument on a venue
+#[derive(Debug, Clone)]
+pub struct TradeFile {
+    pub source: EventSource,
+    pub kind: InstrumentKind,
+    /// Venue-native symbol of the trades, dumps usually only carry it in the file name
+    pub symbol: String,
+    /// CSV files may be gzip compressed, detected by a `.gz` extension, or zipped as
+    /// data.binance.vision serves them, detected by a `.zip` extension. Only the first entry of
+    /// a zip archive is read
+    pub path: PathBuf,
+    pub format: TradeFileFormat,
+}
+
+/// Streams trades from historical dumps, merged across files into a single time-ordered stream
+///
+/// Each file must be ordered by time, as exchange dumps are. Files are read row by row on a
+/// blocking thread, so memory use does not grow with file size. Rows that cannot be parsed or
+/// whose symbol is not registered are logged and skipped.
+///
+/// Timestamps in seconds, microseconds or nanoseconds are normalized to milliseconds, as
+/// data.binance.vision switched spot dumps to microseconds in 2025.
+pub struct HistoricalTradeCollector {
+    registry: Arc<InstrumentRegistry>,
+    files: Vec<TradeFile>,
+}
+
+impl HistoricalTradeCollector {
+    pub fn new(registry: InstrumentRegistry) -> Self {
+        Self {
+            registry: Arc::new(registry),
+            files: Vec::new(),
+        }
+    }
+
+    pub fn with_file(
+        mut self,
+        source: EventSource,
+        kind: InstrumentKind,
+        symbol: &str,
+        path: impl Into<PathBuf>,
+        format: TradeFileFormat,
+    ) -> Self {
+        self.files.push(TradeFile {
+            source,
+            kind,
+            symbol: symbol.to_string(),
+            path: path.into(),
+            format,
+        });
+        self
+    }
+
+    /// Instruments the registered files resolve to
+    pub fn instruments(&self) -> impl Iterator<Item = &Instrument> {
+        self.files
+            .iter()
+            .filter_map(|file| self.registry.resolve(&file.source, file.kind, &file.symbol))
+    }
+}
+
+#[async_trait::async_trait]
+impl Collector<InternalEvent> for HistoricalTradeCollector {
+    fn name(&self) -> &'static str {
+        "historical_trade_collector"
+    }
+
+    async fn get_event_stream(&self) -> Result<CollectorStream<'_, InternalEvent>, CollectorError> {
+        if self.files.is_empty() {
+            return Err(CollectorError::SubscriptionRejected(
+                "Historical: no trade files configured".to_string(),
+            ));
+        }
+
+        let mut sources = Vec::with_capacity(self.files.len());
+        for file in &self.files {
+            let trades = open_trades(file, self.registry.clone()).map_err(|e| {
+                CollectorError::Connect(format!("Historical: {}: {}", file.path.display(), e))
+            })?;
+            sources.push(trades);
+        }
+
+        let (tx, rx) = mpsc::channel(READ_AHEAD);
+        tokio::task::spawn_blocking(move || merge_trades(sources, tx));
+
+        Ok(Box::pin(ReceiverStream::new(rx).map(InternalEvent::Trade)))
+    }
+
+    fn is_finite(&self) -> bool {
+        true
+    }
+
+    fn reconnect_policy(&self) -> ReconnectPolicy {
+        ReconnectPolicy {
+            max_retries: Some(0),
+            ..ReconnectPolicy::default()
+        }
+    }
+}
+
+/// K-way merge of time-ordered trade iterators, ties broken by file order
+fn merge_trades(mut sources: Vec<TradeIter>, tx: mpsc::Sender<Trade>) {
+    let mut heads = sources
+        .iter_mut()
+        .map(|trades| trades.next())
+        .collect::<Vec<_>>();
+
+    while let Some(index) = heads
+        .iter()
+        .enumerate()
+        .filter_map(|(index, head)| head.as_ref().map(|trade| (index, trade.timestamp)))
+        .min_by_key(|(index, timestamp)| (*timestamp, *index))
+        .map(|(index, _)| index)
+    {
+        let Some(trade) = heads[index].take() else {
+            break;
+        };
+        heads[index] = sources[index].next();
+
+        if tx.blocking_send(trade).is_err() {
+            return;
+        }
+    }
+}
+
+fn open_trades(file: &TradeFile, registry: Arc<InstrumentRegistry>) -> anyhow::Result<TradeIter> {
+    match file.format {
+        TradeFileFormat::BinanceTrades => csv_trades(file, registry, CsvColumns::Fixed(1, 2, 4)),
+        TradeFileFormat::BinanceAggTrades => csv_trades(file, registry, CsvColumns::Fixed(1, 2, 5)),
+        TradeFileFormat::CoinbaseTrades => {
+            csv_trades(file, registry, CsvColumns::Named("price", "size", "time"))
+        }
+        #[cfg(feature = "parquet")]
+        TradeFileFormat::Parquet => parquet_trades(file, registry),
+    }
+}
+
+enum CsvColumns {
+    /// Positions of the price, size and time columns, a leading header row is skipped if present
+    Fixed(usize, usize, usize),
+    /// Header names of the price, size and time columns
+    Named(&'static str, &'static str, &'static str),
+}
+
+fn csv_trades(
+    file: &TradeFile,
+    registry: Arc<InstrumentRegistry>,
+    columns: CsvColumns,
+) -> anyhow::Result<TradeIter> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(matches!(columns, CsvColumns::Named(..)))
+        .flexible(true)
+        .from_reader(open_file(&file.path)?);
+
+    let (price, size, time, optional_header) = match columns {
+        CsvColumns::Fixed(price, size, time) => (price, size, time, true),
+        CsvColumns::Named(price, size, time) => {
+            let headers = reader.headers()?;
+            let position = |name: &str| {
+                headers
+                    .iter()
+                    .position(|header| header.trim().eq_ignore_ascii_case(name))
+                    .ok_or_else(|| anyhow::anyhow!("Missing '{}' column", name))
+            };
+            (position(price)?, position(size)?, position(time)?, false)
+        }
+    };
+
+    let file = file.clone();
+    let trades = reader
+        .into_records()
+        .enumerate()
+        .filter_map(move |(index, record)| {
+            let record = match record {
+                Ok(record) => record,
+                Err(e) => {
+                    tracing::warn!(
+                        "Skipping row {} of {}: {}",
+                        index + 1,
+                        file.path.display(),
+                        e
+                    );
+                    return None;
+                }
+            };
+            let field = |column: usize| record.get(column).unwrap_or_default().trim();
+
+            // Newer dumps start with a header row, older ones do not
+            if index == 0 && optional_header && field(price).parse::<f64>().is_err() {
+                return None;
+            }
+
+            to_trade(&file, &registry, field(price), field(size), field(time))
+                .inspect_err(|e| {
+                    tracing::warn!(
+                        "Skipping row {} of {}: {}",
+                        index + 1,
+                        file.path.display(),
+                        e
+                    )
+                })
+                .ok()
+        });
+
+    Ok(Box::new(trades))
+}
+
+#[cfg(feature = "parquet")]
+fn parquet_trades(
+    file: &TradeFile,
+    registry: Arc<InstrumentRegistry>,
+) -> anyhow::Result<TradeIter> {
+    use parquet::{
+        file::reader::SerializedFileReader,
+        record::{Field, Row},
+    };
+
+    fn column(row: &Row, names: &[&str]) -> Option<String> {
+        row.get_column_iter()
+            .find(|(name, _)| names.iter().any(|n| name.eq_ignore_ascii_case(n)))
+            .and_then(|(_, field)| match field {
+                Field::Str(value) => Some(value.clone()),
+                Field::Double(value) => Some(value.to_string()),
+                Field::Float(value) => Some(value.to_string()),
+                Field::Long(value) => Some(value.to_string()),
+                Field::Int(value) => Some(value.to_string()),
+                Field::ULong(value) => Some(value.to_string()),
+                Field::TimestampMillis(value) => Some(value.to_string()),
+                Field::TimestampMicros(value) => Some(value.to_string()),
+                _ => None,
+            })
+    }
+
+    let reader = SerializedFileReader::new(File::open(&file.path)?)?;
+
+    let file = file.clone();
+    let trades = reader
+        .into_iter()
+        .enumerate()
+        .filter_map(move |(index, row)| {
+            let result = row.map_err(anyhow::Error::from).and_then(|row| {
+                let price = column(&row, &["price"]);
+                let size = column(&row, &["size", "qty", "quantity"]);
+                let time = column(&row, &["time", "timestamp", "transact_time"]);
+                match (price, size, time) {
+                    (Some(price), Some(size), Some(time)) => {
+                        to_trade(&file, &registry, &price, &size, &time)
+                    }
+                    _ => Err(anyhow::anyhow!("Missing price, size or time column")),
+                }
+            });
+
+            result
+                .inspect_err(|e| {
+                    tracing::warn!(
+                        "Skipping row {} of {}: {}",
+                        index + 1,
+                        file.path.display(),
+                        e
+                    )
+                })
+                .ok()
+        });
+
+    Ok(Box::new(trades))
+}
+
+fn to_trade(
+    file: &TradeFile,
+    registry: &InstrumentRegistry,
+    price: &str,
+    size: &str,
+    time: &str,
+) -> anyhow::Result<Trade> {
+    let timestamp = parse_timestamp(time)?;
+    Trade::from_raw(
+        file.source.clone(),
+        file.kind,
+        &file.symbol,
+        price,
+        size,
+        timestamp,
+        registry,
+    )
+}
+
+/// Parses an epoch timestamp of any common precision, or an RFC 3339 time, into milliseconds
+fn parse_timestamp(time: &str) -> anyhow::Result<u64> {
+    let Ok(value) = time.parse::<u64>() else {
+        return parse_iso8601_to_timestamp(time)
+            .map_err(|e| anyhow::anyhow!("Invalid time '{}': {}", time, e));
+    };
+
+    Ok(match value {
+        0..100_000_000_000 => value * 1_000,
+        100_000_000_000..100_000_000_000_000 => value,
+        100_000_000_000_000..100_000_000_000_000_000 => value / 1_000,
+        _ => value / 1_000_000,
+    })
+}
+
+fn open_file(path: &Path) -> io::Result<Box<dyn Read + Send>> {
+    let file = File::open(path)?;
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("gz") => Ok(Box::new(MultiGzDecoder::new(file))),
+        Some("zip") => open_zip_entry(file),
+        _ => Ok(Box::new(file)),
+    }
+}
+
+/// Streams the first entry of a zip archive from its local header, without reading the central
+/// directory at the end of the archive
+fn open_zip_entry(mut file: File) -> io::Result<Box<dyn Read + Send>> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+
+    let mut header = [0; 30];
+    file.read_exact(&mut header)?;
+    if header[..4] != ZIP_LOCAL_HEADER {
+        return Err(invalid("Not a zip archive".to_string()));
+    }
+    let field = |offset: usize| u16::from_le_bytes([header[offset], header[offset + 1]]);
+    let method = field(8);
+    let compressed_size = u32::from_le_bytes([header[18], header[19], header[20], header[21]]);
+    file.seek(SeekFrom::Current(
+        i64::from(field(26)) + i64::from(field(28)),
+    ))?;
+
+    match method {
+        // Stored entries followed by a data descriptor leave their size out of the header
+        0 if field(6) & 0x08 != 0 => Err(invalid(
+            "Stored zip entry without its size in the local header".to_string(),
+        )),
+        0 => Ok(Box::new(file.take(u64::from(compressed_size)))),
+        8 => Ok(Box::new(DeflateDecoder::new(file))),
+        method => Err(invalid(format!(
+            "Unsupported zip compression method {}, only stored and deflated entries are read",
+            method
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{Compression, write::DeflateEncoder};
+
+    use super::*;
+    use crate::test_support as support;
+
+    const TRADES: &str = "\
+1,100.5,0.1,10.05,1700000000000,true,true
+2,100.6,0.2,20.12,1700000000001,false,true
+";
+
+    fn collector(dir: &Path, name: &str, contents: &[u8]) -> HistoricalTradeCollector {
+        let path = dir.join(name);
+        std::fs::write(&path, contents).unwrap();
+
+        let mut registry = InstrumentRegistry::new();
+        registry.insert(EventSource::Binance, support::instrument());
+        HistoricalTradeCollector::new(registry).with_file(
+            EventSource::Binance,
+            InstrumentKind::Spot,
+            "BTCUSDT",
+            path,
+            TradeFileFormat::BinanceTrades,
+        )
+    }
+
+    async fn collect(collector: &HistoricalTradeCollector) -> Vec<Trade> {
+        collector
+            .get_event_stream()
+            .await
+            .unwrap()
+            .filter_map(|event| match event {
+                InternalEvent::Trade(trade) => Some(trade),
+                _ => None,
+            })
+            .collect()
+            .await
+    }
+
+    fn prices(trades: &[Trade]) -> Vec<(f64, u64)> {
+        trades
+            .iter()
+            .map(|trade| (trade.price, trade.timestamp))
+            .collect()
+    }
+
+    /// Single entry archive as data.binance.vision serves them, without the central directory
+    /// the collector never reads
+    fn zip(contents: &[u8]) -> Vec<u8> {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(contents).unwrap();
+        let deflated = encoder.finish().unwrap();
+        let name = b"BTCUSDT-trades.csv";
+
+        let mut archive = ZIP_LOCAL_HEADER.to_vec();
+        archive.extend(20u16.to_le_bytes());
+        archive.extend(0u16.to_le_bytes());
+        archive.extend(8u16.to_le_bytes());
+        archive.extend([0; 8]);
+        archive.extend((deflated.len() as u32).to_le_bytes());
+        archive.extend((contents.len() as u32).to_le_bytes());
+        archive.extend((name.len() as u16).to_le_bytes());
+        archive.extend(0u16.to_le_bytes());
+        archive.extend(name);
+        archive.extend(deflated);
+        archive
+    }
+
+    #[test]
+    fn timestamps_are_normalized_to_milliseconds() {
+        assert_eq!(parse_timestamp("1700000000").unwrap(), 1_700_000_000_000);
+        assert_eq!(parse_timestamp("1700000000123").unwrap(), 1_700_000_000_123);
+        assert_eq!(
+            parse_timestamp("1700000000123456").unwrap(),
+            1_700_000_000_123
+        );
+        assert_eq!(
+            parse_timestamp("1700000000123456789").unwrap(),
+            1_700_000_000_123
+        );
+        assert_eq!(
+            parse_timestamp("2023-11-14T22:13:20.123Z").unwrap(),
+            1_700_000_000_123
+        );
+        assert!(parse_timestamp("yesterday").is_err());
+    }
+
+    #[tokio::test]
+    async fn csv_headers_are_optional() {
+        let dir = support::temp_dir("historical-headers");
+        let header = "id,price,qty,quote_qty,time,is_buyer_maker,is_best_match\n";
+
+        let without = collect(&collector(&dir, "plain.csv", TRADES.as_bytes())).await;
+        let with = collect(&collector(
+            &dir,
+            "header.csv",
+            format!("{}{}", header, TRADES).as_bytes(),
+        ))
+        .await;
+
+        let expected = vec![(100.5, 1_700_000_000_000), (100.6, 1_700_000_000_001)];
+        assert_eq!(prices(&without), expected);
+        assert_eq!(prices(&with), expected);
+    }
+
+    #[tokio::test]
+    async fn named_columns_are_read_from_the_header() {
+        let dir = support::temp_dir("historical-named");
+        let csv = "trade_id,time,size,price\n1,2023-11-14T22:13:20Z,0.5,99.5\n";
+        let mut collector = collector(&dir, "coinbase.csv", csv.as_bytes());
+        collector.files[0].format = TradeFileFormat::CoinbaseTrades;
+
+        let trades = collect(&collector).await;
+
+        assert_eq!(prices(&trades), vec![(99.5, 1_700_000_000_000)]);
+        assert_eq!(trades[0].size, 0.5);
+    }
+
+    #[tokio::test]
+    async fn compressed_dumps_are_read() {
+        let dir = support::temp_dir("historical-compressed");
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), Compression::default());
+        gz.write_all(TRADES.as_bytes()).unwrap();
+
+        let gzipped = collect(&collector(&dir, "trades.csv.gz", &gz.finish().unwrap())).await;
+        let zipped = collect(&collector(&dir, "trades.zip", &zip(TRADES.as_bytes()))).await;
+
+        assert_eq!(gzipped.len(), 2);
+        assert_eq!(prices(&zipped), prices(&gzipped));
+    }
+
+    #[tokio::test]
+    async fn unsupported_zip_entries_fail_to_open() {
+        let dir = support::temp_dir("historical-zip-method");
+        let mut archive = zip(TRADES.as_bytes());
+        // Bzip2 instead of deflate
+        archive[8] = 12;
+
+        let collector = collector(&dir, "trades.zip", &archive);
+
+        match collector.get_event_stream().await {
+            Err(CollectorError::Connect(message)) => {
+                assert!(message.contains("compression method 12"), "{}", message)
+            }
+            _ => panic!("Zip archive with an unsupported entry opened"),
+        }
+    }
+
+    #[test]
+    fn files_are_merged_by_time_then_file_order() {
+        // Trades are priced at the index of their file
+        let trades = |file: usize, timestamps: &[u64]| -> TradeIter {
+            let trades = timestamps
+                .iter()
+                .map(|&at| support::trade(EventSource::Binance, file as f64, 1.0, at))
+                .collect::<Vec<_>>();
+            Box::new(trades.into_iter())
+        };
+        let (tx, mut rx) = mpsc::channel(16);
+
+        merge_trades(
+            vec![trades(0, &[1, 3, 5]), trades(1, &[]), trades(2, &[2, 3, 4])],
+            tx,
+        );
+
+        let mut merged = Vec::new();
+        while let Ok(trade) = rx.try_recv() {
+            merged.push((trade.timestamp, trade.price));
+        }
+        assert_eq!(
+            merged,
+            vec![(1, 0.0), (2, 2.0), (3, 0.0), (3, 2.0), (4, 2.0), (5, 0.0)]
+        );
+    }
+}
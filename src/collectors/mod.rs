@@ -1,3 +1,12 @@
 pub mod binance;
 pub mod bybit;
 pub mod coinbase;
+pub mod historical;
+pub mod replay;
+
+use crate::models::{CollectorError, InternalEvent};
+
+/// Error event for a message of `venue` that could not be decoded or converted
+fn decode_error(venue: &str, error: impl std::fmt::Display) -> InternalEvent {
+    CollectorError::Decode(format!("{}: {}", venue, error)).into()
+}
@@ -0,0 +1,242 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use flate2::read::MultiGzDecoder;
+use tokio::{sync::mpsc, time::Instant};
+
+use crate::models::{
+    Collector, CollectorError, CollectorStream, InternalEvent, ReconnectPolicy, RecordedEvent,
+};
+
+/// Number of decoded records buffered ahead of the replayed stream
+const READ_AHEAD: usize = 1_024;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplaySpeed {
+    /// Yield events as soon as they are read
+    AsFastAsPossible,
+    /// Preserve the recorded gaps between events
+    RealTime,
+    /// Preserve the recorded gaps shrunk by the given factor, e.g. `10.0` for 10× speed;
+    /// non-positive factors replay as fast as possible
+    Multiple(f64),
+}
+
+impl ReplaySpeed {
+    /// Factor recorded gaps are divided by, `None` when events are not paced
+    fn factor(&self) -> Option<f64> {
+        match self {
+            ReplaySpeed::AsFastAsPossible => None,
+            ReplaySpeed::RealTime => Some(1.0),
+            ReplaySpeed::Multiple(factor) if *factor > 0.0 => Some(*factor),
+            ReplaySpeed::Multiple(_) => None,
+        }
+    }
+}
+
+/// Plays back events captured by the [`FileRecorder`](crate::recorders::file::FileRecorder)
+///
+/// Files are read in the order given, decompressing `.gz` files, and each line is decoded as a
+/// [`RecordedEvent`]. Pacing uses the receive timestamps, so the replay reproduces the timing
+/// the bot originally observed. Lines that cannot be decoded are logged and skipped.
+///
+/// The replay runs once, the collector does not restart it when the stream ends.
+pub struct ReplayCollector {
+    paths: Vec<PathBuf>,
+    speed: ReplaySpeed,
+}
+
+impl ReplayCollector {
+    pub fn new(paths: impl IntoIterator<Item = impl Into<PathBuf>>) -> Self {
+        Self {
+            paths: paths.into_iter().map(Into::into).collect(),
+            speed: ReplaySpeed::AsFastAsPossible,
+        }
+    }
+
+    /// Replays every `.jsonl` and `.jsonl.gz` file in `dir`, ordered by file name
+    ///
+    /// Recorder file names start with the time they were opened, so this is recording order.
+    pub fn from_dir(dir: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let mut paths = std::fs::read_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        paths.retain(|path| {
+            let name = path.to_string_lossy();
+            name.ends_with(".jsonl") || name.ends_with(".jsonl.gz")
+        });
+        paths.sort();
+
+        Ok(Self::new(paths))
+    }
+
+    pub fn with_speed(mut self, speed: ReplaySpeed) -> Self {
+        self.speed = speed;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl Collector<InternalEvent> for ReplayCollector {
+    fn name(&self) -> &'static str {
+        "replay_collector"
+    }
+
+    async fn get_event_stream(&self) -> Result<CollectorStream<'_, InternalEvent>, CollectorError> {
+        if self.paths.is_empty() {
+            return Err(CollectorError::SubscriptionRejected(
+                "Replay: no recordings configured".to_string(),
+            ));
+        }
+        if let Some(missing) = self.paths.iter().find(|path| !path.is_file()) {
+            return Err(CollectorError::Connect(format!(
+                "Replay: recording {} not found",
+                missing.display()
+            )));
+        }
+
+        // Files are read and decoded on a blocking thread, which stops once the stream is dropped
+        let (tx, rx) = mpsc::channel(READ_AHEAD);
+        let paths = self.paths.clone();
+        tokio::task::spawn_blocking(move || read_recordings(paths, tx));
+
+        let factor = self.speed.factor();
+        let stream = futures::stream::unfold(
+            (rx, None::<(u64, Instant)>),
+            move |(mut rx, mut origin)| async move {
+                let record = rx.recv().await?;
+
+                if let Some(factor) = factor {
+                    let (first_received_at, started) =
+                        *origin.get_or_insert((record.received_at, Instant::now()));
+                    let gap_ms = record.received_at.saturating_sub(first_received_at) as f64;
+                    tokio::time::sleep_until(
+                        started + Duration::from_secs_f64(gap_ms / factor / 1_000.0),
+                    )
+                    .await;
+                }
+
+                Some((record.event, (rx, origin)))
+            },
+        );
+
+        Ok(Box::pin(stream))
+    }
+
+    fn is_finite(&self) -> bool {
+        true
+    }
+
+    fn reconnect_policy(&self) -> ReconnectPolicy {
+        ReconnectPolicy {
+            max_retries: Some(0),
+            ..ReconnectPolicy::default()
+        }
+    }
+}
+
+fn read_recordings(paths: Vec<PathBuf>, tx: mpsc::Sender<RecordedEvent<InternalEvent>>) {
+    for path in paths {
+        let reader = match open_recording(&path) {
+            Ok(reader) => reader,
+            Err(e) => {
+                tracing::error!("Failed to open recording {}: {}", path.display(), e);
+                continue;
+            }
+        };
+        tracing::info!("Replaying {}", path.display());
+
+        for (index, line) in reader.lines().enumerate() {
+            // A recording cut short by a crash ends with a partial line or gzip stream
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => {
+                    tracing::warn!("Stopped reading {}: {}", path.display(), e);
+                    break;
+                }
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match serde_json::from_str(&line) {
+                Ok(record) => {
+                    if tx.blocking_send(record).is_err() {
+                        return;
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("Skipping line {} of {}: {}", index + 1, path.display(), e);
+                }
+            }
+        }
+    }
+}
+
+fn open_recording(path: &Path) -> std::io::Result<Box<dyn BufRead + Send>> {
+    let file = File::open(path)?;
+    if path.extension().is_some_and(|extension| extension == "gz") {
+        Ok(Box::new(BufReader::new(MultiGzDecoder::new(file))))
+    } else {
+        Ok(Box::new(BufReader::new(file)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::{
+        models::{EventSource, Recorder},
+        recorders::file::{FileRecorder, FileRecorderConfig},
+        test_support as support,
+    };
+
+    /// Records trades with timestamps `0..count` through the file recorder, rotating after a
+    /// few records
+    fn record(dir: &Path, count: u64, compress: bool) {
+        let mut recorder = FileRecorder::new(FileRecorderConfig {
+            dir: dir.to_path_buf(),
+            max_file_bytes: 512,
+            compress,
+            ..FileRecorderConfig::default()
+        })
+        .unwrap();
+        for timestamp in 0..count {
+            let event =
+                InternalEvent::Trade(support::trade(EventSource::Binance, 100.0, 1.0, timestamp));
+            recorder.record(&event).unwrap();
+        }
+    }
+
+    async fn replay(dir: &Path) -> Vec<u64> {
+        let collector = ReplayCollector::from_dir(dir).unwrap();
+        collector
+            .get_event_stream()
+            .await
+            .unwrap()
+            .map(|event| match event {
+                InternalEvent::Trade(trade) => trade.timestamp,
+                other => panic!("unexpected event {:?}", other),
+            })
+            .collect()
+            .await
+    }
+
+    #[tokio::test]
+    async fn rotated_recordings_replay_in_order() {
+        for compress in [false, true] {
+            let dir = support::temp_dir(&format!("replay-round-trip-{}", compress));
+            record(&dir, 20, compress);
+
+            let files = std::fs::read_dir(&dir).unwrap().count();
+            assert!((2..20).contains(&files), "{} files", files);
+            assert_eq!(replay(&dir).await, (0..20).collect::<Vec<_>>());
+        }
+    }
+}
@@ -0,0 +1,263 @@
+use std::collections::{HashMap, HashSet};
+
+pub use crate::models::book::{BookKey, OrderBook};
+use crate::{
+    metrics::{BotMetrics, DurationRecorder},
+    models::{
+        event::{ConnectionStatus, EventSource, InternalEvent},
+        instrument::Instrument,
+        lag::LagPolicy,
+        output::{BookData, DepthData, StateOutput},
+        traits::{EventPublisher, OneShot, StateEngine},
+    },
+};
+
+#[derive(Debug)]
+pub struct OrderBookStateEngine {
+    /// Basis point bands around the mid reported as depth in outputs
+    depth_bps: Vec<f64>,
+    books: HashMap<BookKey, OrderBook>,
+    /// Sends resync requests to the collectors
+    requests: Option<EventPublisher<InternalEvent>>,
+    /// Sources asked to resubscribe that did not send a snapshot yet
+    resyncing: HashSet<EventSource>,
+}
+
+#[async_trait::async_trait]
+impl StateEngine<InternalEvent, StateOutput> for OrderBookStateEngine {
+    fn name(&self) -> &'static str {
+        "order_book_state_engine"
+    }
+
+    /// Drops every book and asks their collectors for fresh snapshots, as the books can no
+    /// longer be trusted once the engine missed events
+    async fn sync_state(&mut self) -> anyhow::Result<()> {
+        let sources = self
+            .books
+            .iter_mut()
+            .map(|((source, _), book)| {
+                book.invalidate();
+                source.clone()
+            })
+            .collect::<HashSet<_>>();
+
+        for source in sources {
+            self.request_resync(source);
+        }
+        Ok(())
+    }
+
+    fn process_event(&mut self, event: InternalEvent) -> anyhow::Result<()> {
+        let recorder = DurationRecorder::start();
+        let event_type = event.event_type();
+
+        match event {
+            InternalEvent::BookSnapshot(update) => {
+                self.resyncing.remove(&update.source);
+                self.books
+                    .entry((update.source.clone(), update.instrument.clone()))
+                    .or_default()
+                    .apply_snapshot(&update);
+            }
+            InternalEvent::BookDelta(update) => {
+                let book = self
+                    .books
+                    .entry((update.source.clone(), update.instrument.clone()))
+                    .or_default();
+
+                if let Err(e) = book.apply_delta(&update) {
+                    tracing::warn!("{}, resubscribing to resync", e);
+                    BotMetrics::record_book_resync(self.name(), &format!("{:?}", update.source));
+                    self.request_resync(update.source);
+                }
+            }
+            InternalEvent::Connection(source, ConnectionStatus::Disconnected(_)) => {
+                // A new connection starts from a fresh snapshot, so drop the books of the source
+                self.resyncing.remove(&source);
+                self.books
+                    .iter_mut()
+                    .filter(|((book_source, _), _)| *book_source == source)
+                    .for_each(|(_, book)| book.invalidate());
+            }
+            _ => {}
+        }
+
+        let duration = recorder.end();
+        BotMetrics::record_event_processing(self.name(), &event_type, duration);
+
+        Ok(())
+    }
+
+    fn process_request(&self, request: OneShot<StateOutput>) -> anyhow::Result<()> {
+        let data = self
+            .books
+            .iter()
+            .filter(|(_, book)| book.is_synced())
+            .filter_map(|((source, instrument), book)| {
+                let (best_bid, best_ask) = (book.best_bid()?.price, book.best_ask()?.price);
+                let mid = (best_bid + best_ask) / 2.0;
+                let depth = self
+                    .depth_bps
+                    .iter()
+                    .map(|&bps| {
+                        let (bid_size, ask_size) = book.depth_within(mid, bps);
+                        DepthData {
+                            bps,
+                            bid_size,
+                            ask_size,
+                        }
+                    })
+                    .collect();
+
+                Some(BookData {
+                    source: source.clone(),
+                    instrument: instrument.clone(),
+                    best_bid,
+                    best_ask,
+                    mid,
+                    spread: best_ask - best_bid,
+                    depth,
+                })
+            })
+            .collect::<Vec<BookData>>();
+
+        request.respond(StateOutput::Books(data))?;
+        Ok(())
+    }
+
+    fn on_shutdown(&mut self) -> anyhow::Result<()> {
+        tracing::info!("Shutting down OrderBookStateEngine");
+        Ok(())
+    }
+
+    fn lag_policy(&self) -> LagPolicy {
+        LagPolicy::Resync
+    }
+
+    fn set_request_publisher(&mut self, publisher: EventPublisher<InternalEvent>) {
+        self.requests = Some(publisher);
+    }
+}
+
+impl OrderBookStateEngine {
+    pub fn new(depth_bps: Vec<f64>) -> Self {
+        OrderBookStateEngine {
+            depth_bps,
+            books: HashMap::new(),
+            requests: None,
+            resyncing: HashSet::new(),
+        }
+    }
+
+    pub fn book(&self, source: &EventSource, instrument: &Instrument) -> Option<&OrderBook> {
+        self.books.get(&(source.clone(), instrument.clone()))
+    }
+
+    /// Asks the collector of a source to resubscribe, once until a snapshot of it arrives
+    fn request_resync(&mut self, source: EventSource) {
+        if self.resyncing.contains(&source) {
+            return;
+        }
+
+        match &self.requests {
+            Some(requests) => match requests.publish(InternalEvent::Resync(source.clone())) {
+                Ok(()) => {
+                    self.resyncing.insert(source);
+                }
+                Err(e) => tracing::warn!("Failed to request a resync of {:?}: {}", source, e),
+            },
+            None => tracing::warn!(
+                "No collector to resync {:?} from, awaiting snapshot",
+                source
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::broadcast;
+
+    use super::*;
+    use crate::{
+        models::book::{BookUpdate, Level, Sequence},
+        test_support as support,
+    };
+
+    fn update(
+        source: EventSource,
+        bids: &[(f64, f64)],
+        asks: &[(f64, f64)],
+        sequence: Option<(u64, u64)>,
+    ) -> BookUpdate {
+        let levels = |levels: &[(f64, f64)]| {
+            levels
+                .iter()
+                .map(|&(price, size)| Level { price, size })
+                .collect()
+        };
+        BookUpdate {
+            source,
+            instrument: support::instrument(),
+            bids: levels(bids),
+            asks: levels(asks),
+            sequence: sequence.map(|(first, last)| Sequence { first, last }),
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn each_source_is_resynced_once_until_its_snapshot() {
+        let (requests_tx, mut requests) = broadcast::channel(16);
+        let mut engine = OrderBookStateEngine::new(vec![]);
+        engine.set_request_publisher(EventPublisher::new(requests_tx));
+        for source in [EventSource::Binance, EventSource::Bybit] {
+            engine
+                .process_event(InternalEvent::BookSnapshot(update(
+                    source,
+                    &[(99.0, 1.0)],
+                    &[(101.0, 1.0)],
+                    Some((10, 10)),
+                )))
+                .unwrap();
+        }
+        let gap =
+            |source| InternalEvent::BookDelta(update(source, &[(100.0, 1.0)], &[], Some((20, 20))));
+
+        engine.process_event(gap(EventSource::Binance)).unwrap();
+        engine.process_event(gap(EventSource::Binance)).unwrap();
+        engine.process_event(gap(EventSource::Bybit)).unwrap();
+
+        assert!(matches!(
+            requests.try_recv(),
+            Ok(InternalEvent::Resync(EventSource::Binance))
+        ));
+        assert!(matches!(
+            requests.try_recv(),
+            Ok(InternalEvent::Resync(EventSource::Bybit))
+        ));
+        assert!(requests.try_recv().is_err());
+
+        // A snapshot ends the resync, so the next gap asks again
+        engine
+            .process_event(InternalEvent::BookSnapshot(update(
+                EventSource::Binance,
+                &[(99.0, 1.0)],
+                &[(101.0, 1.0)],
+                Some((30, 30)),
+            )))
+            .unwrap();
+        engine
+            .process_event(InternalEvent::BookDelta(update(
+                EventSource::Binance,
+                &[],
+                &[],
+                Some((40, 40)),
+            )))
+            .unwrap();
+        assert!(matches!(
+            requests.try_recv(),
+            Ok(InternalEvent::Resync(EventSource::Binance))
+        ));
+    }
+}
@@ -0,0 +1,511 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Arc,
+};
+
+use crate::{
+    engines::book::OrderBook,
+    metrics::{BotMetrics, DurationRecorder},
+    models::{
+        clock::{LiveClock, SharedClock},
+        event::{ConnectionStatus, EventSource, InternalEvent},
+        instrument::{Instrument, InstrumentKind},
+        output::{FairPriceData, StateOutput},
+        trade::Trade,
+        traits::{OneShot, StateEngine},
+    },
+};
+
+/// How venue prices are weighted into the fair price
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Weighting {
+    /// Traded volume over the configured window
+    Volume,
+    /// Resting size at the top of the book
+    Liquidity,
+    /// Inverse of the smoothed delay between exchange and receive timestamps
+    InverseLatency,
+    /// Median of venue prices, ignoring weights
+    Median,
+}
+
+#[derive(Debug, Clone)]
+pub struct FairPriceConfig {
+    pub weighting: Weighting,
+    /// Venues without a trade for longer than this are dropped
+    pub max_age_ms: u64,
+    /// Venues deviating from the median by more than this are dropped as outliers, once at least
+    /// three venues are fresh
+    pub max_deviation_bps: f64,
+    /// Window over which traded volume is accumulated for volume weighting
+    pub volume_window_ms: u64,
+}
+
+impl Default for FairPriceConfig {
+    fn default() -> Self {
+        Self {
+            weighting: Weighting::Volume,
+            max_age_ms: 5_000,
+            max_deviation_bps: 50.0,
+            volume_window_ms: 60_000,
+        }
+    }
+}
+
+/// Canonical market shared by the same instrument across venues
+type MarketKey = (String, InstrumentKind);
+
+/// Venues needed to tell an outlier apart, with two the median lies halfway between them and
+/// neither can be blamed
+const MIN_VENUES_FOR_OUTLIERS: usize = 3;
+
+#[derive(Debug, Default)]
+struct VenueState {
+    last_price: f64,
+    last_trade_at: u64,
+    /// Recent trades as `(timestamp, size)` within the volume window
+    volume: VecDeque<(u64, f64)>,
+    /// Exponentially smoothed receive delay in milliseconds
+    latency_ms: Option<f64>,
+    book: OrderBook,
+}
+
+impl VenueState {
+    fn weight(&self, weighting: Weighting) -> f64 {
+        match weighting {
+            Weighting::Volume => self.volume.iter().map(|(_, size)| size).sum(),
+            Weighting::Liquidity => {
+                let bid = self.book.best_bid().map_or(0.0, |level| level.size);
+                let ask = self.book.best_ask().map_or(0.0, |level| level.size);
+                bid + ask
+            }
+            Weighting::InverseLatency => self
+                .latency_ms
+                .map_or(0.0, |latency| 1.0 / latency.max(1.0)),
+            Weighting::Median => 1.0,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct FairPriceEngine {
+    config: FairPriceConfig,
+    venues: HashMap<MarketKey, HashMap<EventSource, VenueState>>,
+    /// Sources whose collector is currently disconnected, excluded from outputs
+    stale: HashSet<EventSource>,
+    clock: SharedClock,
+}
+
+#[async_trait::async_trait]
+impl StateEngine<InternalEvent, StateOutput> for FairPriceEngine {
+    fn name(&self) -> &'static str {
+        "fair_price_engine"
+    }
+
+    async fn sync_state(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn process_event(&mut self, event: InternalEvent) -> anyhow::Result<()> {
+        let recorder = DurationRecorder::start();
+        let event_type = event.event_type();
+
+        match event {
+            InternalEvent::Trade(trade) => self.add_trade(trade),
+            InternalEvent::BookSnapshot(update) => {
+                self.venue_mut(&update.source, &update.instrument)
+                    .book
+                    .apply_snapshot(&update);
+            }
+            InternalEvent::BookDelta(update) => {
+                let venue = self.venue_mut(&update.source, &update.instrument);
+                if let Err(e) = venue.book.apply_delta(&update) {
+                    tracing::debug!("Dropping book for liquidity weighting: {}", e);
+                }
+            }
+            InternalEvent::Connection(source, status) => match status {
+                ConnectionStatus::Disconnected(_) => {
+                    self.stale.insert(source);
+                }
+                ConnectionStatus::Reconnected => {
+                    self.stale.remove(&source);
+                }
+            },
+            _ => {}
+        }
+
+        let duration = recorder.end();
+        BotMetrics::record_event_processing(self.name(), &event_type, duration);
+
+        Ok(())
+    }
+
+    fn process_request(&self, request: OneShot<StateOutput>) -> anyhow::Result<()> {
+        let now = self.clock.now_ms();
+        let data = self
+            .venues
+            .iter()
+            .filter_map(|(market, venues)| self.calc_fair_price(market, venues, now))
+            .collect::<Vec<FairPriceData>>();
+
+        request.respond(StateOutput::FairPrices(data))?;
+        Ok(())
+    }
+
+    fn on_shutdown(&mut self) -> anyhow::Result<()> {
+        tracing::info!("Shutting down FairPriceEngine");
+        Ok(())
+    }
+}
+
+impl FairPriceEngine {
+    pub fn new(config: FairPriceConfig) -> Self {
+        FairPriceEngine {
+            config,
+            venues: HashMap::new(),
+            stale: HashSet::new(),
+            clock: Arc::new(LiveClock),
+        }
+    }
+
+    /// Measures staleness and latency against `clock` instead of wall time
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn add_trade(&mut self, trade: Trade) {
+        let received_at = self.clock.now_ms();
+        let window = self.config.volume_window_ms;
+        let venue = self.venue_mut(&trade.source, &trade.instrument);
+
+        venue.last_price = trade.price;
+        venue.last_trade_at = trade.timestamp;
+        venue.volume.push_back((trade.timestamp, trade.size));
+        while let Some(&(timestamp, _)) = venue.volume.front() {
+            if timestamp + window >= trade.timestamp {
+                break;
+            }
+            venue.volume.pop_front();
+        }
+
+        let latency = received_at.saturating_sub(trade.timestamp) as f64;
+        venue.latency_ms = Some(match venue.latency_ms {
+            Some(smoothed) => smoothed * 0.9 + latency * 0.1,
+            None => latency,
+        });
+    }
+
+    /// Combines the fresh, non-outlier venues of a market into a fair price
+    fn calc_fair_price(
+        &self,
+        (canonical, kind): &MarketKey,
+        venues: &HashMap<EventSource, VenueState>,
+        now: u64,
+    ) -> Option<FairPriceData> {
+        // Venues only known from their books never traded, and bad prints of zero or less
+        // cannot be compared against, so neither counts
+        let eligible = venues
+            .iter()
+            .filter(|(_, venue)| venue.last_price > 0.0)
+            .collect::<Vec<_>>();
+        let fresh = eligible
+            .iter()
+            .filter(|(source, venue)| {
+                !self.stale.contains(*source)
+                    && now.saturating_sub(venue.last_trade_at) <= self.config.max_age_ms
+            })
+            .copied()
+            .collect::<Vec<_>>();
+
+        let reference = median(fresh.iter().map(|(_, venue)| venue.last_price).collect())?;
+        let reject_outliers = fresh.len() >= MIN_VENUES_FOR_OUTLIERS;
+        let included = fresh
+            .into_iter()
+            .filter(|(_, venue)| {
+                !reject_outliers
+                    || deviation_bps(venue.last_price, reference) <= self.config.max_deviation_bps
+            })
+            .collect::<Vec<_>>();
+        if included.is_empty() {
+            // The venues disagree without a majority around the median
+            return None;
+        }
+
+        let prices = included
+            .iter()
+            .map(|(_, venue)| venue.last_price)
+            .collect::<Vec<_>>();
+        let mut weights = included
+            .iter()
+            .map(|(_, venue)| venue.weight(self.config.weighting))
+            .collect::<Vec<_>>();
+        if weights.iter().sum::<f64>() <= 0.0 {
+            weights = vec![1.0; prices.len()];
+        }
+        let total_weight = weights.iter().sum::<f64>();
+
+        let price = match self.config.weighting {
+            Weighting::Median => median(prices.clone())?,
+            _ => {
+                prices
+                    .iter()
+                    .zip(&weights)
+                    .map(|(price, weight)| price * weight)
+                    .sum::<f64>()
+                    / total_weight
+            }
+        };
+
+        let variance = prices
+            .iter()
+            .zip(&weights)
+            .map(|(p, weight)| weight * (p - price).powi(2))
+            .sum::<f64>()
+            / total_weight;
+
+        Some(FairPriceData {
+            canonical: canonical.clone(),
+            kind: *kind,
+            price,
+            dispersion_bps: variance.sqrt() / price * 10_000.0,
+            confidence: included.len() as f64 / eligible.len() as f64,
+            sources: included
+                .iter()
+                .map(|(source, _)| (*source).clone())
+                .collect(),
+        })
+    }
+
+    fn venue_mut(&mut self, source: &EventSource, instrument: &Instrument) -> &mut VenueState {
+        self.venues
+            .entry((instrument.canonical(), instrument.kind))
+            .or_default()
+            .entry(source.clone())
+            .or_default()
+    }
+}
+
+fn median(mut values: Vec<f64>) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+
+    values.sort_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        Some((values[mid - 1] + values[mid]) / 2.0)
+    } else {
+        Some(values[mid])
+    }
+}
+
+fn deviation_bps(price: f64, reference: f64) -> f64 {
+    (price - reference).abs() / reference * 10_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{
+            book::{BookUpdate, Level},
+            clock::SimulatedClock,
+            error::CollectorError,
+        },
+        test_support::{self as support, assert_close},
+    };
+
+    const NOW: u64 = 1_000_000;
+
+    fn engine(weighting: Weighting) -> FairPriceEngine {
+        FairPriceEngine::new(FairPriceConfig {
+            weighting,
+            ..Default::default()
+        })
+        .with_clock(Arc::new(SimulatedClock::new(NOW)))
+    }
+
+    fn trade(engine: &mut FairPriceEngine, source: EventSource, price: f64, size: f64, at: u64) {
+        engine.add_trade(support::trade(source, price, size, at));
+    }
+
+    fn book(engine: &mut FairPriceEngine, source: EventSource, price: f64, size: f64) {
+        let level = |price| Level { price, size };
+        let update = BookUpdate {
+            source,
+            instrument: support::instrument(),
+            bids: vec![level(price - 0.5)],
+            asks: vec![level(price + 0.5)],
+            sequence: None,
+            timestamp: NOW,
+        };
+        engine
+            .process_event(InternalEvent::BookSnapshot(update))
+            .unwrap();
+    }
+
+    fn fair_price(engine: &FairPriceEngine) -> Option<FairPriceData> {
+        match support::request(engine) {
+            StateOutput::FairPrices(mut data) => data.pop(),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn volume_weighting_favours_traded_size() {
+        let mut engine = engine(Weighting::Volume);
+        trade(&mut engine, EventSource::Binance, 100.0, 3.0, NOW);
+        trade(&mut engine, EventSource::Bybit, 100.4, 1.0, NOW);
+
+        let fair = fair_price(&engine).unwrap();
+        assert_close(fair.price, 100.1);
+        assert_close(fair.confidence, 1.0);
+        assert_eq!(fair.sources.len(), 2);
+    }
+
+    #[test]
+    fn volume_weighting_forgets_trades_outside_the_window() {
+        let mut engine = engine(Weighting::Volume);
+        trade(&mut engine, EventSource::Binance, 100.0, 9.0, NOW - 70_000);
+        trade(&mut engine, EventSource::Binance, 100.0, 1.0, NOW);
+        trade(&mut engine, EventSource::Bybit, 100.4, 1.0, NOW);
+
+        assert_close(fair_price(&engine).unwrap().price, 100.2);
+    }
+
+    #[test]
+    fn liquidity_weighting_uses_top_of_book_size() {
+        let mut engine = engine(Weighting::Liquidity);
+        trade(&mut engine, EventSource::Binance, 100.0, 1.0, NOW);
+        trade(&mut engine, EventSource::Bybit, 100.4, 1.0, NOW);
+        book(&mut engine, EventSource::Binance, 100.0, 3.0);
+        book(&mut engine, EventSource::Bybit, 100.4, 1.0);
+
+        assert_close(fair_price(&engine).unwrap().price, 100.1);
+    }
+
+    #[test]
+    fn inverse_latency_weighting_favours_fast_venues() {
+        let mut engine = engine(Weighting::InverseLatency);
+        trade(&mut engine, EventSource::Binance, 100.0, 1.0, NOW - 10);
+        trade(&mut engine, EventSource::Bybit, 100.11, 1.0, NOW - 100);
+
+        // Weights of 1/10 and 1/100
+        assert_close(fair_price(&engine).unwrap().price, 100.01);
+    }
+
+    #[test]
+    fn zero_weights_fall_back_to_equal_weights() {
+        let mut engine = engine(Weighting::Liquidity);
+        trade(&mut engine, EventSource::Binance, 100.0, 1.0, NOW);
+        trade(&mut engine, EventSource::Bybit, 100.2, 1.0, NOW);
+
+        assert_close(fair_price(&engine).unwrap().price, 100.1);
+    }
+
+    #[test]
+    fn median_weighting_ignores_weights() {
+        let mut engine = engine(Weighting::Median);
+        trade(&mut engine, EventSource::Binance, 100.0, 10.0, NOW);
+        trade(&mut engine, EventSource::Bybit, 100.2, 1.0, NOW);
+        trade(&mut engine, EventSource::Coinbase, 100.3, 1.0, NOW);
+
+        assert_close(fair_price(&engine).unwrap().price, 100.2);
+    }
+
+    #[test]
+    fn single_venue_has_zero_dispersion() {
+        let mut engine = engine(Weighting::Volume);
+        trade(&mut engine, EventSource::Binance, 100.0, 1.0, NOW);
+
+        let fair = fair_price(&engine).unwrap();
+        assert_close(fair.price, 100.0);
+        assert_close(fair.dispersion_bps, 0.0);
+    }
+
+    #[test]
+    fn outlier_is_dropped() {
+        let mut engine = engine(Weighting::Volume);
+        trade(&mut engine, EventSource::Binance, 100.0, 1.0, NOW);
+        trade(&mut engine, EventSource::Bybit, 100.2, 1.0, NOW);
+        trade(&mut engine, EventSource::Coinbase, 110.0, 1.0, NOW);
+
+        let fair = fair_price(&engine).unwrap();
+        assert_close(fair.price, 100.1);
+        assert_close(fair.confidence, 2.0 / 3.0);
+        assert!(!fair.sources.contains(&EventSource::Coinbase));
+    }
+
+    #[test]
+    fn two_diverging_venues_are_both_kept() {
+        let mut engine = engine(Weighting::Volume);
+        trade(&mut engine, EventSource::Binance, 100.0, 1.0, NOW);
+        trade(&mut engine, EventSource::Bybit, 110.0, 1.0, NOW);
+
+        let fair = fair_price(&engine).unwrap();
+        assert_close(fair.price, 105.0);
+        assert_close(fair.confidence, 1.0);
+        assert!(fair.dispersion_bps > engine.config.max_deviation_bps);
+    }
+
+    #[test]
+    fn non_positive_prices_are_skipped() {
+        let mut engine = engine(Weighting::Volume);
+        trade(&mut engine, EventSource::Binance, 0.0, 1.0, NOW);
+        trade(&mut engine, EventSource::Bybit, -1.0, 1.0, NOW);
+        trade(&mut engine, EventSource::Coinbase, 100.0, 1.0, NOW);
+
+        let fair = fair_price(&engine).unwrap();
+        assert_close(fair.price, 100.0);
+        assert_close(fair.dispersion_bps, 0.0);
+        assert_close(fair.confidence, 1.0);
+        assert_eq!(fair.sources, vec![EventSource::Coinbase]);
+    }
+
+    #[test]
+    fn no_fair_price_when_every_venue_is_rejected() {
+        let mut engine = engine(Weighting::Volume);
+        // Bad prints are skipped before they can skew the median or be divided by
+        trade(&mut engine, EventSource::Binance, 0.0, 1.0, NOW);
+        trade(&mut engine, EventSource::Bybit, -1.0, 1.0, NOW);
+
+        assert!(fair_price(&engine).is_none());
+    }
+
+    #[test]
+    fn confidence_ignores_venues_that_never_traded() {
+        let mut engine = engine(Weighting::Volume);
+        trade(&mut engine, EventSource::Binance, 100.0, 1.0, NOW);
+        trade(&mut engine, EventSource::Bybit, 100.2, 1.0, NOW);
+        book(&mut engine, EventSource::Coinbase, 100.0, 1.0);
+
+        assert_close(fair_price(&engine).unwrap().confidence, 1.0);
+    }
+
+    #[test]
+    fn stale_and_disconnected_venues_are_excluded() {
+        let mut engine = engine(Weighting::Volume);
+        trade(&mut engine, EventSource::Binance, 100.0, 1.0, NOW);
+        trade(&mut engine, EventSource::Bybit, 101.0, 1.0, NOW - 10_000);
+        trade(&mut engine, EventSource::Coinbase, 102.0, 1.0, NOW);
+        engine
+            .process_event(InternalEvent::Connection(
+                EventSource::Coinbase,
+                ConnectionStatus::Disconnected(CollectorError::StreamClosed),
+            ))
+            .unwrap();
+
+        let fair = fair_price(&engine).unwrap();
+        assert_close(fair.price, 100.0);
+        assert_eq!(fair.sources, vec![EventSource::Binance]);
+    }
+
+    #[test]
+    fn no_fair_price_without_fresh_venues() {
+        let mut engine = engine(Weighting::Volume);
+        trade(&mut engine, EventSource::Binance, 100.0, 1.0, NOW - 10_000);
+
+        assert!(fair_price(&engine).is_none());
+    }
+}
@@ -1 +1,3 @@
+pub mod book;
+pub mod fair;
 pub mod price;
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use mizuhiki_ta::{core::CandleSeries, indicators::Config as MizuhikiConfig};
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
@@ -6,17 +6,24 @@ use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use crate::{
     metrics::{BotMetrics, DurationRecorder},
     models::{
-        event::{EventSource, InternalEvent},
+        event::{ConnectionStatus, EventSource, InternalEvent},
+        instrument::Instrument,
         output::{PriceData, StateOutput},
         trade::Trade,
         traits::{OneShot, StateEngine},
     },
 };
 
+/// Candle series key, a source and the instrument traded on it
+pub type MarketKey = (EventSource, Instrument);
+
 #[derive(Debug)]
 pub struct PriceStateEngine {
+    timeframe: u64,
     indicator_config: MizuhikiConfig<f64>,
-    candles: HashMap<EventSource, CandleSeries<f64>>,
+    candles: HashMap<MarketKey, CandleSeries<f64>>,
+    /// Sources whose collector is currently disconnected, excluded from outputs
+    stale: HashSet<EventSource>,
 }
 
 #[async_trait::async_trait]
@@ -37,6 +44,16 @@ impl StateEngine<InternalEvent, StateOutput> for PriceStateEngine {
             InternalEvent::Trade(trade) => {
                 self.add_trade(trade)?;
             }
+            InternalEvent::Connection(source, status) => match status {
+                ConnectionStatus::Disconnected(e) => {
+                    tracing::warn!("Marking {:?} as stale: {}", source, e);
+                    self.stale.insert(source);
+                }
+                ConnectionStatus::Reconnected => {
+                    tracing::info!("Marking {:?} as live", source);
+                    self.stale.remove(&source);
+                }
+            },
             InternalEvent::Error(e) => {
                 tracing::error!("Error processing Binance event: {}", e);
                 return Err(anyhow::anyhow!("Error processing Binance event: {}", e));
@@ -57,11 +74,13 @@ impl StateEngine<InternalEvent, StateOutput> for PriceStateEngine {
         let data = self
             .candles
             .par_iter()
-            .filter_map(|(source, candle_series)| {
-                let rsi = self.calc_rsi(source);
-                let natr = self.calc_natr(source);
+            .filter(|((source, _), _)| !self.stale.contains(source))
+            .filter_map(|(key, candle_series)| {
+                let rsi = self.calc_rsi(key);
+                let natr = self.calc_natr(key);
                 let price = candle_series.closes().last().cloned();
-                PriceData::try_new(source.clone(), price, rsi, natr)
+                let (source, instrument) = key.clone();
+                PriceData::try_new(source, instrument, price, rsi, natr)
             })
             .collect::<Vec<PriceData>>();
 
@@ -72,9 +91,13 @@ impl StateEngine<InternalEvent, StateOutput> for PriceStateEngine {
     fn on_shutdown(&mut self) -> anyhow::Result<()> {
         tracing::info!("Shutting down BinanceStateEngine");
 
-        println!("Final State:");
-        for (source, candle_series) in &self.candles {
-            println!("Source: {:?}, Candles: {}", source, candle_series.len());
+        for ((source, instrument), candle_series) in &self.candles {
+            tracing::info!(
+                "Final state of {:?} {}: {} candles",
+                source,
+                instrument,
+                candle_series.len()
+            );
         }
         Ok(())
     }
@@ -82,34 +105,28 @@ impl StateEngine<InternalEvent, StateOutput> for PriceStateEngine {
 
 impl PriceStateEngine {
     pub fn new(timeframe: u64) -> Self {
-        let candles = EventSource::get_all()
-            .into_iter()
-            .map(|source| (source, CandleSeries::new(timeframe)))
-            .collect();
         PriceStateEngine {
+            timeframe,
             indicator_config: MizuhikiConfig::default(),
-            candles,
+            candles: HashMap::new(),
+            stale: HashSet::new(),
         }
     }
 
     pub fn add_trade(&mut self, trade: Trade) -> anyhow::Result<()> {
-        match self.candles.get_mut(&trade.source) {
-            Some(candle_series) => {
-                candle_series.push(trade.price, trade.size, trade.timestamp)?;
-            }
-            None => {
-                return Err(anyhow::anyhow!(
-                    "No candle series found for source: {:?}",
-                    trade.source
-                ));
-            }
-        }
+        let timeframe = self.timeframe;
+        let candle_series = self
+            .candles
+            .entry((trade.source, trade.instrument))
+            .or_insert_with(|| CandleSeries::new(timeframe));
+
+        candle_series.push(trade.price, trade.size, trade.timestamp)?;
         Ok(())
     }
 
-    pub fn calc_rsi(&self, source: &EventSource) -> Option<f64> {
+    pub fn calc_rsi(&self, key: &MarketKey) -> Option<f64> {
         let result =
-            mizuhiki_ta::indicators::rsi_latest(&self.candles[source], &self.indicator_config);
+            mizuhiki_ta::indicators::rsi_latest(&self.candles[key], &self.indicator_config);
 
         match result {
             Ok(rsi) => Some(rsi),
@@ -120,9 +137,9 @@ impl PriceStateEngine {
         }
     }
 
-    pub fn calc_natr(&self, source: &EventSource) -> Option<f64> {
+    pub fn calc_natr(&self, key: &MarketKey) -> Option<f64> {
         let result =
-            mizuhiki_ta::indicators::natr_latest(&self.candles[source], &self.indicator_config);
+            mizuhiki_ta::indicators::natr_latest(&self.candles[key], &self.indicator_config);
 
         match result {
             Ok(natr) => Some(natr),
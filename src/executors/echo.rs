@@ -4,12 +4,15 @@ use crate::models::Executor;
 pub struct EchoExecutor;
 
 #[async_trait::async_trait]
-impl Executor<String> for EchoExecutor {
+impl<A, E> Executor<A, E> for EchoExecutor
+where
+    A: std::fmt::Display + Send + Sync + 'static,
+{
     fn name(&self) -> &'static str {
         "echo_executor"
     }
 
-    async fn execute(&self, action: String) -> anyhow::Result<()> {
+    async fn execute(&self, action: A) -> anyhow::Result<()> {
         println!("Executing action: {}", action);
         Ok(())
     }
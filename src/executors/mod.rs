@@ -1 +1,3 @@
 pub mod echo;
+pub mod order_manager;
+pub mod paper;
@@ -0,0 +1,441 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    metrics::BotMetrics,
+    models::{
+        ActionFilter, EventPublisher, Executor, InternalEvent, OneShot, OrderData, OrdersData,
+        QueuePolicy, StateEngine, StateOutput,
+        order::{Action, AmendRequest, ExecutionKind, ExecutionReport, Fill, OrderStatus},
+    },
+};
+
+/// Number of fills kept for strategies to inspect
+const MAX_RECENT_FILLS: usize = 1_000;
+
+#[derive(Debug, Default)]
+struct OrderBookkeeping {
+    next_id: u64,
+    orders: HashMap<String, OrderData>,
+    fills: VecDeque<Fill>,
+}
+
+impl OrderBookkeeping {
+    /// Moves an order to `next`, ignoring transitions the lifecycle does not allow
+    ///
+    /// Orders reaching a terminal status are dropped, their fills remain in the recent fills.
+    fn transition(&mut self, client_id: &str, next: OrderStatus) {
+        let Some(order) = self.orders.get_mut(client_id) else {
+            tracing::warn!("Status {:?} for unknown order {}", next, client_id);
+            return;
+        };
+
+        // Fills can be reported before the submission returns, the ack is then redundant
+        if next == OrderStatus::Acked && order.status != OrderStatus::New {
+            return;
+        }
+
+        if order.status.can_transition_to(next) {
+            order.status = next;
+            if next.is_terminal() {
+                tracing::info!("Order {} is {:?}", client_id, next);
+                self.orders.remove(client_id);
+            }
+        } else {
+            tracing::warn!(
+                "Ignoring transition of order {} from {:?} to {:?}",
+                client_id,
+                order.status,
+                next
+            );
+        }
+    }
+
+    /// Moves an order to `next` once its submission returned, unless execution reports already
+    /// moved it past `New` or closed it in the meantime
+    fn submitted(&mut self, client_id: &str, next: OrderStatus) {
+        match self.orders.get(client_id) {
+            Some(order) if order.status == OrderStatus::New => self.transition(client_id, next),
+            _ => tracing::debug!(
+                "Order {} moved on before its submission returned, keeping its status",
+                client_id
+            ),
+        }
+    }
+
+    fn apply_fill(&mut self, fill: Fill) {
+        let Some(order) = self.orders.get_mut(&fill.client_id) else {
+            tracing::warn!("Fill for unknown order {}", fill.client_id);
+            return;
+        };
+
+        let filled = order.filled_quantity + fill.quantity;
+        order.avg_fill_price =
+            (order.avg_fill_price * order.filled_quantity + fill.price * fill.quantity) / filled;
+        order.filled_quantity = filled;
+
+        let next = if filled >= order.request.quantity - f64::EPSILON {
+            OrderStatus::Filled
+        } else {
+            OrderStatus::PartiallyFilled
+        };
+        let client_id = fill.client_id.clone();
+
+        self.fills.push_back(fill);
+        if self.fills.len() > MAX_RECENT_FILLS {
+            self.fills.pop_front();
+        }
+        self.transition(&client_id, next);
+    }
+
+    /// Applies an amend the executor accepted
+    fn amend(&mut self, amend: &AmendRequest) {
+        let Some(order) = self.orders.get_mut(&amend.client_id) else {
+            tracing::debug!("Order {} closed before its amend applied", amend.client_id);
+            return;
+        };
+
+        if let Some(price) = amend.price {
+            order.request.price = Some(price);
+        }
+        if let Some(quantity) = amend.quantity {
+            order.request.quantity = quantity;
+        }
+    }
+
+    fn reconcile(&mut self, report: ExecutionReport) {
+        match report.kind {
+            ExecutionKind::Acked => self.transition(&report.client_id, OrderStatus::Acked),
+            ExecutionKind::Fill(fill) => self.apply_fill(fill),
+            ExecutionKind::Cancelled => self.transition(&report.client_id, OrderStatus::Cancelled),
+            // Once accepted, only a cancel or amend of the order can be rejected
+            ExecutionKind::Rejected(reason) => match self.orders.get(&report.client_id) {
+                Some(order) if order.status != OrderStatus::New => {
+                    tracing::warn!(
+                        "Cancel or amend of order {} rejected: {}",
+                        report.client_id,
+                        reason
+                    );
+                }
+                _ => {
+                    tracing::warn!("Order {} rejected: {}", report.client_id, reason);
+                    self.transition(&report.client_id, OrderStatus::Rejected);
+                }
+            },
+        }
+    }
+}
+
+/// Executor wrapper tracking every order it forwards through its lifecycle
+///
+/// Orders are recorded when the bot routes them, before they are queued, and those without a
+/// client id are assigned one. Successful submissions are marked acked and failures rejected,
+/// amends are applied once the executor accepted them. Fills and cancels
+/// are reconciled from execution reports by the [`OrderManagerEngine`], which also exposes
+/// open orders and fills to strategies.
+pub struct OrderManager {
+    prefix: String,
+    inner: Box<dyn Executor<Action, InternalEvent>>,
+    state: Arc<Mutex<OrderBookkeeping>>,
+}
+
+impl OrderManager {
+    pub fn new(prefix: &str, inner: Box<dyn Executor<Action, InternalEvent>>) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+            inner,
+            state: Arc::new(Mutex::new(OrderBookkeeping::default())),
+        }
+    }
+
+    /// State engine reconciling execution reports, must be registered with the bot
+    pub fn engine(&self) -> OrderManagerEngine {
+        OrderManagerEngine {
+            state: self.state.clone(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Executor<Action, InternalEvent> for OrderManager {
+    fn name(&self) -> &'static str {
+        "order_manager"
+    }
+
+    async fn execute(&self, action: Action) -> anyhow::Result<()> {
+        let client_id = action.client_id().to_string();
+        let result = self.inner.execute(action.clone()).await;
+
+        let mut state = self.state.lock().expect("Order state lock poisoned");
+        // Cancels only take effect once the venue reports the order cancelled
+        match (&action, &result) {
+            (Action::Order(_), Ok(())) => state.submitted(&client_id, OrderStatus::Acked),
+            (Action::Order(_), Err(_)) => state.submitted(&client_id, OrderStatus::Rejected),
+            (Action::Amend(amend), Ok(())) => state.amend(amend),
+            _ => {}
+        }
+
+        if result.is_err() {
+            BotMetrics::record_error(self.inner.name());
+        }
+        result
+    }
+
+    fn queue_policy(&self) -> QueuePolicy {
+        self.inner.queue_policy()
+    }
+
+    /// Records a new order as soon as it is routed, assigning a client id when the strategy left
+    /// it empty, and checks that amended orders are open. Queued orders are therefore already
+    /// part of the order state and can be amended or cancelled
+    fn register(&self, action: &mut Action) -> anyhow::Result<()> {
+        let mut state = self.state.lock().expect("Order state lock poisoned");
+
+        match action {
+            Action::Order(order) => {
+                if order.client_id.is_empty() {
+                    state.next_id += 1;
+                    order.client_id = format!("{}-{}", self.prefix, state.next_id);
+                }
+                if state.orders.contains_key(&order.client_id) {
+                    return Err(anyhow::anyhow!("Duplicate client id {}", order.client_id));
+                }
+
+                state.orders.insert(
+                    order.client_id.clone(),
+                    OrderData {
+                        request: order.clone(),
+                        status: OrderStatus::New,
+                        filled_quantity: 0.0,
+                        avg_fill_price: 0.0,
+                    },
+                );
+            }
+            Action::Amend(amend) => {
+                if !state.orders.contains_key(&amend.client_id) {
+                    return Err(anyhow::anyhow!("No open order with id {}", amend.client_id));
+                }
+            }
+            Action::Cancel(_) => {}
+        }
+
+        Ok(())
+    }
+
+    /// Orders dropped from the queue were never submitted, they are closed as rejected
+    fn discard(&self, action: &Action) {
+        if let Action::Order(order) = action {
+            tracing::warn!("Order {} dropped before submission", order.client_id);
+            self.state
+                .lock()
+                .expect("Order state lock poisoned")
+                .transition(&order.client_id, OrderStatus::Rejected);
+        }
+    }
+
+    fn action_key(&self, action: &Action) -> Option<String> {
+        self.inner.action_key(action)
+    }
+
+    fn coalesce_key(&self, action: &Action) -> Option<String> {
+        self.inner.coalesce_key(action)
+    }
+
+    fn dependency_key(&self, action: &Action) -> Option<String> {
+        self.inner.dependency_key(action)
+    }
+
+    fn action_filter(&self) -> ActionFilter {
+        self.inner.action_filter()
+    }
+
+    fn set_event_publisher(&mut self, publisher: EventPublisher<InternalEvent>) {
+        self.inner.set_event_publisher(publisher);
+    }
+}
+
+/// Reconciles execution reports and reports open orders and recent fills
+#[derive(Debug)]
+pub struct OrderManagerEngine {
+    state: Arc<Mutex<OrderBookkeeping>>,
+}
+
+#[async_trait::async_trait]
+impl StateEngine<InternalEvent, StateOutput> for OrderManagerEngine {
+    fn name(&self) -> &'static str {
+        "order_manager_engine"
+    }
+
+    async fn sync_state(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn process_event(&mut self, event: InternalEvent) -> anyhow::Result<()> {
+        if let InternalEvent::ExecutionReport(report) = event {
+            self.state
+                .lock()
+                .expect("Order state lock poisoned")
+                .reconcile(report);
+        }
+        Ok(())
+    }
+
+    fn process_request(&self, request: OneShot<StateOutput>) -> anyhow::Result<()> {
+        let state = self.state.lock().expect("Order state lock poisoned");
+        let data = OrdersData {
+            open: state.orders.values().cloned().collect(),
+            fills: state.fills.iter().cloned().collect(),
+        };
+        drop(state);
+
+        request.respond(StateOutput::Orders(data))?;
+        Ok(())
+    }
+
+    fn on_shutdown(&mut self) -> anyhow::Result<()> {
+        let state = self.state.lock().expect("Order state lock poisoned");
+        tracing::info!(
+            "Shutting down OrderManagerEngine with {} open orders",
+            state.orders.len()
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{
+            event::EventSource,
+            order::{AmendRequest, OrderRequest, Side},
+        },
+        test_support::{self as support, fill_report},
+    };
+
+    /// Venue accepting every action
+    struct Venue;
+
+    #[async_trait::async_trait]
+    impl Executor<Action, InternalEvent> for Venue {
+        fn name(&self) -> &'static str {
+            "venue"
+        }
+
+        async fn execute(&self, _action: Action) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn manager() -> (OrderManager, OrderManagerEngine) {
+        let manager = OrderManager::new("test", Box::new(Venue));
+        let engine = manager.engine();
+        (manager, engine)
+    }
+
+    fn order(client_id: &str) -> Action {
+        Action::Order(OrderRequest::limit(
+            client_id,
+            EventSource::Binance,
+            support::instrument(),
+            Side::Buy,
+            100.0,
+            2.0,
+        ))
+    }
+
+    fn amend(client_id: &str, price: f64) -> Action {
+        Action::Amend(AmendRequest {
+            client_id: client_id.to_string(),
+            source: EventSource::Binance,
+            instrument: support::instrument(),
+            price: Some(price),
+            quantity: None,
+            strategy_id: None,
+        })
+    }
+
+    fn open_orders(engine: &OrderManagerEngine) -> Vec<OrderData> {
+        match support::request(engine) {
+            StateOutput::Orders(data) => data.open,
+            _ => unreachable!(),
+        }
+    }
+
+    fn fill(engine: &mut OrderManagerEngine, client_id: &str, quantity: f64) {
+        let fill = support::fill(client_id, Side::Buy, 100.0, quantity, 0.0, 0);
+        engine
+            .process_event(InternalEvent::ExecutionReport(fill_report(fill)))
+            .unwrap();
+    }
+
+    #[test]
+    fn routed_orders_are_open_before_execution() {
+        let (manager, engine) = manager();
+        let mut action = order("");
+        manager.register(&mut action).unwrap();
+
+        assert_eq!(action.client_id(), "test-1");
+        let open = open_orders(&engine);
+        assert_eq!(open.len(), 1);
+        assert_eq!(open[0].status, OrderStatus::New);
+
+        // The queued order can already be amended, and cannot be submitted twice
+        manager.register(&mut amend("test-1", 101.0)).unwrap();
+        assert!(manager.register(&mut action).is_err());
+    }
+
+    #[test]
+    fn amends_of_unknown_orders_are_refused() {
+        let (manager, _engine) = manager();
+        assert!(manager.register(&mut amend("missing", 101.0)).is_err());
+    }
+
+    #[tokio::test]
+    async fn submission_acks_and_amends_apply_once_accepted() {
+        let (manager, engine) = manager();
+        let mut action = order("a");
+        manager.register(&mut action).unwrap();
+        manager.execute(action).await.unwrap();
+        assert_eq!(open_orders(&engine)[0].status, OrderStatus::Acked);
+
+        let mut action = amend("a", 99.0);
+        manager.register(&mut action).unwrap();
+        assert_eq!(open_orders(&engine)[0].request.price, Some(100.0));
+        manager.execute(action).await.unwrap();
+        assert_eq!(open_orders(&engine)[0].request.price, Some(99.0));
+    }
+
+    #[tokio::test]
+    async fn reports_before_the_submission_returns_are_kept() {
+        let (manager, mut engine) = manager();
+
+        let mut partial = order("partial");
+        manager.register(&mut partial).unwrap();
+        fill(&mut engine, "partial", 1.0);
+        manager.execute(partial).await.unwrap();
+        assert_eq!(open_orders(&engine)[0].status, OrderStatus::PartiallyFilled);
+
+        let mut filled = order("filled");
+        manager.register(&mut filled).unwrap();
+        fill(&mut engine, "filled", 2.0);
+        manager.execute(filled).await.unwrap();
+        assert!(
+            open_orders(&engine)
+                .iter()
+                .all(|o| o.request.client_id != "filled")
+        );
+    }
+
+    #[test]
+    fn discarded_orders_are_closed() {
+        let (manager, engine) = manager();
+        let mut action = order("a");
+        manager.register(&mut action).unwrap();
+        manager.discard(&action);
+
+        assert!(open_orders(&engine).is_empty());
+    }
+}
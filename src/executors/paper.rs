@@ -0,0 +1,972 @@
+use std::{
+    collections::HashMap,
+    fmt::Write as _,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    engines::book::{BookKey, OrderBook},
+    models::{
+        EventPublisher, EventSource, Executor, Instrument, InternalEvent, LiveClock, OneShot,
+        PortfolioData, PositionData, SharedClock, StateEngine, StateOutput,
+        book::Level,
+        order::{
+            Action, AmendRequest, CancelRequest, ExecutionKind, ExecutionReport, Fill, Liquidity,
+            OrderRequest, OrderType, Side, TimeInForce,
+        },
+        trade::Trade,
+    },
+};
+
+#[derive(Debug, Clone)]
+pub enum SlippageModel {
+    None,
+    /// Fixed adverse move applied to the reference price, in basis points
+    FixedBps(f64),
+    /// Walk the latest observed book, falling back to the last trade when no book is synced
+    BookDepth,
+}
+
+#[derive(Debug, Clone)]
+pub struct PaperConfig {
+    /// Delay between receiving an order and simulating its fill
+    pub latency_ms: u64,
+    pub maker_fee_bps: f64,
+    pub taker_fee_bps: f64,
+    pub slippage: SlippageModel,
+    /// Starting balance per asset, e.g. `USDT`
+    pub initial_balances: HashMap<String, f64>,
+}
+
+impl Default for PaperConfig {
+    fn default() -> Self {
+        Self {
+            latency_ms: 50,
+            maker_fee_bps: 1.0,
+            taker_fee_bps: 5.0,
+            slippage: SlippageModel::FixedBps(1.0),
+            initial_balances: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Position {
+    /// Signed quantity, negative when short
+    pub quantity: f64,
+    pub avg_price: f64,
+    /// Realized PnL in the quote asset, excluding fees
+    pub realized_pnl: f64,
+}
+
+impl Position {
+    pub(crate) fn apply(&mut self, side: Side, price: f64, quantity: f64) {
+        let signed = side.sign() * quantity;
+
+        if self.quantity == 0.0 || self.quantity.signum() == signed.signum() {
+            let total = self.quantity.abs() + quantity;
+            self.avg_price = (self.avg_price * self.quantity.abs() + price * quantity) / total;
+            self.quantity += signed;
+            return;
+        }
+
+        let closing = self.quantity.abs().min(quantity);
+        self.realized_pnl += closing * (price - self.avg_price) * self.quantity.signum();
+        self.quantity += signed;
+
+        if self.quantity == 0.0 {
+            self.avg_price = 0.0;
+        } else if self.quantity.signum() == signed.signum() {
+            // Position flipped, the remainder was opened at the fill price
+            self.avg_price = price;
+        }
+    }
+}
+
+/// Snapshot of the simulated account
+#[derive(Debug, Clone)]
+pub struct PaperReport {
+    pub balances: HashMap<String, f64>,
+    pub positions: HashMap<BookKey, Position>,
+    pub fills: Vec<Fill>,
+    pub fees_paid: f64,
+}
+
+/// Resting limit order and the quantity filled so far
+#[derive(Debug)]
+struct OpenOrder {
+    order: OrderRequest,
+    filled: f64,
+}
+
+#[derive(Debug)]
+struct PaperState {
+    books: HashMap<BookKey, OrderBook>,
+    /// Price and size of the last trade, the only liquidity known without a synced book
+    last_trades: HashMap<BookKey, Level>,
+    open_orders: Vec<OpenOrder>,
+    balances: HashMap<String, f64>,
+    positions: HashMap<BookKey, Position>,
+    fills: Vec<Fill>,
+    /// Bus for execution reports, set once the executor is started by the bot
+    publisher: Option<EventPublisher<InternalEvent>>,
+    /// Time source of report and fill timestamps and of the simulated latency
+    clock: SharedClock,
+}
+
+impl PaperState {
+    fn report(&self, order: &OrderRequest, kind: ExecutionKind) {
+        self.publish(&order.client_id, &order.source, &order.instrument, kind);
+    }
+
+    fn publish(
+        &self,
+        client_id: &str,
+        source: &EventSource,
+        instrument: &Instrument,
+        kind: ExecutionKind,
+    ) {
+        let Some(publisher) = &self.publisher else {
+            return;
+        };
+
+        let report = ExecutionReport {
+            client_id: client_id.to_string(),
+            source: source.clone(),
+            instrument: instrument.clone(),
+            kind,
+            timestamp: self.clock.now_ms(),
+        };
+        if let Err(e) = publisher.publish(InternalEvent::ExecutionReport(report)) {
+            tracing::debug!("Dropping paper execution report: {}", e);
+        }
+    }
+
+    fn submit(&mut self, order: OrderRequest, config: &PaperConfig) -> anyhow::Result<()> {
+        if order.quantity <= 0.0 {
+            return Err(anyhow::anyhow!(
+                "Invalid order quantity: {}",
+                order.quantity
+            ));
+        }
+
+        let key = (order.source.clone(), order.instrument.clone());
+        let limit = match order.order_type {
+            OrderType::Market => None,
+            _ => Some(order.price.ok_or_else(|| {
+                anyhow::anyhow!("Limit order {} without a price", order.client_id)
+            })?),
+        };
+        let taker = self.taker_fill(&key, order.side, order.quantity, limit, config);
+
+        if limit.is_none() && taker.is_none() {
+            return Err(anyhow::anyhow!("No market data for {:?} {}", key.0, key.1));
+        }
+        if taker.is_some() && order.order_type == OrderType::PostOnly {
+            return Err(anyhow::anyhow!(
+                "Post-only order {} would take liquidity",
+                order.client_id
+            ));
+        }
+        // Fill-or-kill orders only take liquidity covering them in full
+        let taker = taker.filter(|&(_, quantity)| {
+            order.time_in_force != TimeInForce::FillOrKill
+                || quantity >= order.quantity - f64::EPSILON
+        });
+
+        self.report(&order, ExecutionKind::Acked);
+        let filled = match taker {
+            Some((price, quantity)) => {
+                self.fill(&order, price, quantity, Liquidity::Taker, config);
+                quantity
+            }
+            None => 0.0,
+        };
+        self.rest(order, filled);
+
+        Ok(())
+    }
+
+    /// Leaves the unfilled remainder of an order resting, or cancels it for immediate orders
+    fn rest(&mut self, order: OrderRequest, filled: f64) {
+        if filled >= order.quantity - f64::EPSILON {
+            return;
+        }
+
+        if order.is_immediate() {
+            tracing::info!(
+                "Paper order {} not marketable for {}, cancelled",
+                order.client_id,
+                order.quantity - filled
+            );
+            self.report(&order, ExecutionKind::Cancelled);
+        } else {
+            self.open_orders.push(OpenOrder { order, filled });
+        }
+    }
+
+    fn cancel(&mut self, cancel: &CancelRequest) -> anyhow::Result<()> {
+        let index = self.open_order(&cancel.client_id)?;
+
+        let open = self.open_orders.remove(index);
+        self.report(&open.order, ExecutionKind::Cancelled);
+        Ok(())
+    }
+
+    /// Replaces the price and/or total quantity of a resting order, an amended price crossing
+    /// the market takes liquidity like a new order would
+    fn amend(&mut self, amend: &AmendRequest, config: &PaperConfig) -> anyhow::Result<()> {
+        let index = self.open_order(&amend.client_id)?;
+        let OpenOrder { order, filled } = &self.open_orders[index];
+        let filled = *filled;
+
+        let mut order = order.clone();
+        if let Some(quantity) = amend.quantity {
+            if quantity <= filled {
+                return Err(anyhow::anyhow!(
+                    "Amended quantity {} of order {} does not exceed its filled quantity {}",
+                    quantity,
+                    order.client_id,
+                    filled
+                ));
+            }
+            order.quantity = quantity;
+        }
+        if let Some(price) = amend.price {
+            if price <= 0.0 {
+                return Err(anyhow::anyhow!(
+                    "Invalid amended price {} of order {}",
+                    price,
+                    order.client_id
+                ));
+            }
+            order.price = Some(price);
+        }
+
+        let key = (order.source.clone(), order.instrument.clone());
+        let taker = self.taker_fill(
+            &key,
+            order.side,
+            order.quantity - filled,
+            order.price,
+            config,
+        );
+        if taker.is_some() && order.order_type == OrderType::PostOnly {
+            return Err(anyhow::anyhow!(
+                "Amended post-only order {} would take liquidity",
+                order.client_id
+            ));
+        }
+
+        self.open_orders.remove(index);
+        self.report(&order, ExecutionKind::Acked);
+        let filled = match taker {
+            Some((price, quantity)) => {
+                self.fill(&order, price, quantity, Liquidity::Taker, config);
+                filled + quantity
+            }
+            None => filled,
+        };
+        self.rest(order, filled);
+
+        Ok(())
+    }
+
+    fn open_order(&self, client_id: &str) -> anyhow::Result<usize> {
+        self.open_orders
+            .iter()
+            .position(|open| open.order.client_id == client_id)
+            .ok_or_else(|| anyhow::anyhow!("No open paper order with id {}", client_id))
+    }
+
+    /// Fills resting limit orders crossed by a trade at their limit price, in the order they
+    /// were placed and up to the traded size
+    fn on_trade(&mut self, trade: Trade, config: &PaperConfig) {
+        let key = (trade.source, trade.instrument);
+        self.last_trades.insert(
+            key.clone(),
+            Level {
+                price: trade.price,
+                size: trade.size,
+            },
+        );
+
+        let mut remaining = trade.size;
+        let mut resting = Vec::new();
+        for mut open in std::mem::take(&mut self.open_orders) {
+            let crossed = (open.order.source.clone(), open.order.instrument.clone()) == key
+                && open.order.price.is_some_and(|limit| match open.order.side {
+                    Side::Buy => trade.price <= limit,
+                    Side::Sell => trade.price >= limit,
+                });
+            if crossed && remaining > 0.0 {
+                let quantity = (open.order.quantity - open.filled).min(remaining);
+                let price = open.order.price.unwrap_or(trade.price);
+                self.fill(&open.order, price, quantity, Liquidity::Maker, config);
+                remaining -= quantity;
+                open.filled += quantity;
+            }
+            if open.filled < open.order.quantity - f64::EPSILON {
+                resting.push(open);
+            }
+        }
+        self.open_orders = resting;
+    }
+
+    /// Price and quantity a taker order gets within `limit`, walking the levels of a synced book
+    /// or else the last trade, neither filling more than they show
+    fn taker_fill(
+        &self,
+        key: &BookKey,
+        side: Side,
+        quantity: f64,
+        limit: Option<f64>,
+        config: &PaperConfig,
+    ) -> Option<(f64, f64)> {
+        let book_levels = self
+            .books
+            .get(key)
+            .filter(|book| book.is_synced())
+            .map(|book| match side {
+                Side::Buy => book.asks().collect::<Vec<_>>(),
+                Side::Sell => book.bids().collect(),
+            })
+            .filter(|levels| !levels.is_empty());
+        let levels = match book_levels {
+            Some(levels) => levels,
+            None => vec![*self.last_trades.get(key)?],
+        };
+
+        let marketable = |price: f64| {
+            limit.is_none_or(|limit| match side {
+                Side::Buy => price <= limit,
+                Side::Sell => price >= limit,
+            })
+        };
+        let (filled, cost) = levels
+            .iter()
+            .take_while(|level| marketable(level.price))
+            .fold((0.0, 0.0), |(filled, cost), level| {
+                let take = level.size.min(quantity - filled);
+                (filled + take, cost + take * level.price)
+            });
+        if filled <= 0.0 {
+            return None;
+        }
+
+        let top = levels[0].price;
+        let price = match &config.slippage {
+            SlippageModel::None => top,
+            SlippageModel::FixedBps(bps) => top * (1.0 + side.sign() * bps / 10_000.0),
+            SlippageModel::BookDepth => cost / filled,
+        };
+        marketable(price).then_some((price, filled))
+    }
+
+    fn fill(
+        &mut self,
+        order: &OrderRequest,
+        price: f64,
+        quantity: f64,
+        liquidity: Liquidity,
+        config: &PaperConfig,
+    ) {
+        let fee_bps = match liquidity {
+            Liquidity::Maker => config.maker_fee_bps,
+            Liquidity::Taker => config.taker_fee_bps,
+        };
+        let notional = price * quantity;
+        let fee = notional * fee_bps / 10_000.0;
+
+        let base = self
+            .balances
+            .entry(order.instrument.base.clone())
+            .or_default();
+        *base += order.side.sign() * quantity;
+        let quote = self
+            .balances
+            .entry(order.instrument.quote.clone())
+            .or_default();
+        *quote -= order.side.sign() * notional + fee;
+
+        self.positions
+            .entry((order.source.clone(), order.instrument.clone()))
+            .or_default()
+            .apply(order.side, price, quantity);
+
+        let fill = Fill {
+            client_id: order.client_id.clone(),
+            source: order.source.clone(),
+            instrument: order.instrument.clone(),
+            side: order.side,
+            price,
+            quantity,
+            fee,
+            liquidity,
+            timestamp: self.clock.now_ms(),
+            strategy_id: order.strategy_id.clone(),
+        };
+        tracing::info!("Paper fill: {:?}", fill);
+        self.report(order, ExecutionKind::Fill(fill.clone()));
+        self.fills.push(fill);
+    }
+}
+
+/// Executor simulating fills against market data observed by its [`PaperMarketEngine`]
+#[derive(Debug)]
+pub struct PaperExecutor {
+    config: PaperConfig,
+    state: Arc<Mutex<PaperState>>,
+}
+
+impl PaperExecutor {
+    pub fn new(config: PaperConfig) -> Self {
+        let state = PaperState {
+            books: HashMap::new(),
+            last_trades: HashMap::new(),
+            open_orders: Vec::new(),
+            balances: config.initial_balances.clone(),
+            positions: HashMap::new(),
+            fills: Vec::new(),
+            publisher: None,
+            clock: Arc::new(LiveClock),
+        };
+
+        Self {
+            config,
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    /// Timestamps fills and waits out the latency on `clock` instead of wall time
+    pub fn with_clock(self, clock: SharedClock) -> Self {
+        self.state.lock().expect("Paper state lock poisoned").clock = clock;
+        self
+    }
+
+    /// State engine feeding market data into this executor, must be registered with the bot
+    pub fn market_engine(&self) -> PaperMarketEngine {
+        PaperMarketEngine {
+            config: self.config.clone(),
+            state: self.state.clone(),
+        }
+    }
+
+    pub fn report(&self) -> PaperReport {
+        let state = self.state.lock().expect("Paper state lock poisoned");
+        PaperReport {
+            balances: state.balances.clone(),
+            positions: state.positions.clone(),
+            fills: state.fills.clone(),
+            fees_paid: state.fills.iter().map(|fill| fill.fee).sum(),
+        }
+    }
+
+    /// Writes all simulated fills to a CSV file
+    pub fn export_fills_csv(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let mut csv = String::from(
+            "timestamp,client_id,source,instrument,side,price,quantity,fee,liquidity\n",
+        );
+        for fill in self.report().fills {
+            writeln!(
+                csv,
+                "{},{},{:?},{},{:?},{},{},{},{:?}",
+                fill.timestamp,
+                fill.client_id,
+                fill.source,
+                fill.instrument,
+                fill.side,
+                fill.price,
+                fill.quantity,
+                fill.fee,
+                fill.liquidity
+            )?;
+        }
+
+        std::fs::write(path, csv)?;
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl Executor<Action, InternalEvent> for PaperExecutor {
+    fn name(&self) -> &'static str {
+        "paper_executor"
+    }
+
+    async fn execute(&self, action: Action) -> anyhow::Result<()> {
+        let clock = self
+            .state
+            .lock()
+            .expect("Paper state lock poisoned")
+            .clock
+            .clone();
+        clock
+            .sleep_until(clock.now_ms() + self.config.latency_ms)
+            .await;
+
+        let mut state = self.state.lock().expect("Paper state lock poisoned");
+        let result = match &action {
+            Action::Order(order) => state.submit(order.clone(), &self.config),
+            Action::Cancel(cancel) => state.cancel(cancel),
+            Action::Amend(amend) => state.amend(amend, &self.config),
+        };
+        if let Err(e) = &result {
+            state.publish(
+                action.client_id(),
+                action.source(),
+                action.instrument(),
+                ExecutionKind::Rejected(e.to_string()),
+            );
+        }
+        result
+    }
+
+    /// Actions on the same instrument execute in order, so cancels never overtake their orders
+    fn action_key(&self, action: &Action) -> Option<String> {
+        Some(format!("{:?} {}", action.source(), action.instrument()))
+    }
+
+    fn coalesce_key(&self, action: &Action) -> Option<String> {
+        action.coalesce_key()
+    }
+
+    fn dependency_key(&self, action: &Action) -> Option<String> {
+        action.order_key()
+    }
+
+    fn set_event_publisher(&mut self, publisher: EventPublisher<InternalEvent>) {
+        self.state
+            .lock()
+            .expect("Paper state lock poisoned")
+            .publisher = Some(publisher);
+    }
+}
+
+/// Tracks trades and books for a [`PaperExecutor`] and reports its simulated portfolio
+#[derive(Debug)]
+pub struct PaperMarketEngine {
+    config: PaperConfig,
+    state: Arc<Mutex<PaperState>>,
+}
+
+#[async_trait::async_trait]
+impl StateEngine<InternalEvent, StateOutput> for PaperMarketEngine {
+    fn name(&self) -> &'static str {
+        "paper_market_engine"
+    }
+
+    async fn sync_state(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn process_event(&mut self, event: InternalEvent) -> anyhow::Result<()> {
+        let mut state = self.state.lock().expect("Paper state lock poisoned");
+
+        match event {
+            InternalEvent::Trade(trade) => state.on_trade(trade, &self.config),
+            InternalEvent::BookSnapshot(update) => {
+                state
+                    .books
+                    .entry((update.source.clone(), update.instrument.clone()))
+                    .or_default()
+                    .apply_snapshot(&update);
+            }
+            InternalEvent::BookDelta(update) => {
+                let book = state
+                    .books
+                    .entry((update.source.clone(), update.instrument.clone()))
+                    .or_default();
+                if let Err(e) = book.apply_delta(&update) {
+                    tracing::debug!("Paper book out of sync: {}", e);
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn process_request(&self, request: OneShot<StateOutput>) -> anyhow::Result<()> {
+        let state = self.state.lock().expect("Paper state lock poisoned");
+        let positions = state
+            .positions
+            .iter()
+            .map(|((source, instrument), position)| PositionData {
+                source: source.clone(),
+                instrument: instrument.clone(),
+                quantity: position.quantity,
+                avg_price: position.avg_price,
+                realized_pnl: position.realized_pnl,
+            })
+            .collect();
+
+        let data = PortfolioData {
+            balances: state.balances.clone(),
+            positions,
+            open_orders: state.open_orders.len(),
+        };
+        drop(state);
+
+        request.respond(StateOutput::Portfolio(data))?;
+        Ok(())
+    }
+
+    fn on_shutdown(&mut self) -> anyhow::Result<()> {
+        tracing::info!("Shutting down PaperMarketEngine");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::FutureExt;
+    use tokio::sync::broadcast;
+
+    use super::*;
+    use crate::{
+        models::{book::BookUpdate, clock::SimulatedClock},
+        test_support::{self as support, assert_close},
+    };
+
+    const NOW: u64 = 1_000_000;
+
+    struct Paper {
+        executor: PaperExecutor,
+        engine: PaperMarketEngine,
+        clock: Arc<SimulatedClock>,
+        reports: broadcast::Receiver<InternalEvent>,
+    }
+
+    impl Paper {
+        fn new(config: PaperConfig) -> Self {
+            let clock = Arc::new(SimulatedClock::new(NOW));
+            let (reports_tx, reports) = broadcast::channel(64);
+            let mut executor = PaperExecutor::new(config).with_clock(clock.clone());
+            executor.set_event_publisher(EventPublisher::new(reports_tx));
+            let engine = executor.market_engine();
+            Self {
+                executor,
+                engine,
+                clock,
+                reports,
+            }
+        }
+
+        fn trade(&mut self, price: f64, size: f64) {
+            let trade = support::trade(EventSource::Binance, price, size, NOW);
+            self.engine
+                .process_event(InternalEvent::Trade(trade))
+                .unwrap();
+        }
+
+        fn book(&mut self, bids: &[(f64, f64)], asks: &[(f64, f64)]) {
+            let levels = |levels: &[(f64, f64)]| {
+                levels
+                    .iter()
+                    .map(|&(price, size)| Level { price, size })
+                    .collect()
+            };
+            let update = BookUpdate {
+                source: EventSource::Binance,
+                instrument: support::instrument(),
+                bids: levels(bids),
+                asks: levels(asks),
+                sequence: None,
+                timestamp: NOW,
+            };
+            self.engine
+                .process_event(InternalEvent::BookSnapshot(update))
+                .unwrap();
+        }
+
+        fn execute(&self, action: Action) -> anyhow::Result<()> {
+            self.executor
+                .execute(action)
+                .now_or_never()
+                .expect("Paper executor waited without latency")
+        }
+
+        /// Kinds of the reports published since the last call
+        fn reports(&mut self) -> Vec<ExecutionKind> {
+            std::iter::from_fn(|| self.reports.try_recv().ok())
+                .filter_map(|event| match event {
+                    InternalEvent::ExecutionReport(report) => Some(report.kind),
+                    _ => None,
+                })
+                .collect()
+        }
+
+        fn fills(&self) -> Vec<Fill> {
+            self.executor.report().fills
+        }
+
+        fn open_orders(&self) -> usize {
+            match support::request(&self.engine) {
+                StateOutput::Portfolio(portfolio) => portfolio.open_orders,
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    fn config(slippage: SlippageModel) -> PaperConfig {
+        PaperConfig {
+            latency_ms: 0,
+            slippage,
+            ..Default::default()
+        }
+    }
+
+    fn market(side: Side, quantity: f64) -> Action {
+        Action::Order(OrderRequest::market(
+            "1",
+            EventSource::Binance,
+            support::instrument(),
+            side,
+            quantity,
+        ))
+    }
+
+    fn limit(side: Side, price: f64, quantity: f64) -> OrderRequest {
+        OrderRequest::limit(
+            "1",
+            EventSource::Binance,
+            support::instrument(),
+            side,
+            price,
+            quantity,
+        )
+    }
+
+    fn amend(price: Option<f64>, quantity: Option<f64>) -> Action {
+        Action::Amend(AmendRequest {
+            client_id: "1".to_string(),
+            source: EventSource::Binance,
+            instrument: support::instrument(),
+            price,
+            quantity,
+            strategy_id: None,
+        })
+    }
+
+    fn cancel(client_id: &str) -> Action {
+        Action::Cancel(CancelRequest {
+            client_id: client_id.to_string(),
+            source: EventSource::Binance,
+            instrument: support::instrument(),
+            strategy_id: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn orders_execute_after_the_latency() {
+        let mut paper = Paper::new(PaperConfig {
+            latency_ms: 50,
+            ..config(SlippageModel::None)
+        });
+        paper.trade(100.0, 1.0);
+
+        let execute = paper.executor.execute(market(Side::Buy, 1.0));
+        tokio::pin!(execute);
+        assert!(execute.as_mut().now_or_never().is_none());
+        assert!(paper.fills().is_empty());
+
+        paper.clock.advance_by(50);
+        execute.await.unwrap();
+        assert_eq!(paper.fills()[0].timestamp, NOW + 50);
+    }
+
+    #[test]
+    fn fees_depend_on_liquidity() {
+        let mut paper = Paper::new(config(SlippageModel::None));
+        paper.trade(100.0, 1.0);
+
+        paper.execute(market(Side::Buy, 1.0)).unwrap();
+        paper
+            .execute(Action::Order(limit(Side::Sell, 101.0, 1.0)))
+            .unwrap();
+        paper.trade(101.0, 1.0);
+
+        let fills = paper.fills();
+        assert_eq!(fills[0].liquidity, Liquidity::Taker);
+        assert_close(fills[0].fee, 100.0 * 5.0 / 10_000.0);
+        assert_eq!(fills[1].liquidity, Liquidity::Maker);
+        assert_close(fills[1].fee, 101.0 / 10_000.0);
+        let report = paper.executor.report();
+        assert_close(report.balances["USDT"], 1.0 - 0.05 - 0.0101);
+        assert_close(report.fees_paid, 0.0601);
+    }
+
+    #[test]
+    fn fixed_slippage_moves_the_price_against_the_taker() {
+        let mut paper = Paper::new(config(SlippageModel::FixedBps(10.0)));
+        paper.trade(100.0, 2.0);
+
+        paper.execute(market(Side::Buy, 1.0)).unwrap();
+        paper.execute(market(Side::Sell, 1.0)).unwrap();
+
+        let fills = paper.fills();
+        assert_close(fills[0].price, 100.1);
+        assert_close(fills[1].price, 99.9);
+    }
+
+    #[test]
+    fn book_depth_slippage_walks_the_book() {
+        let mut paper = Paper::new(config(SlippageModel::BookDepth));
+        paper.book(&[(99.0, 1.0)], &[(100.0, 1.0), (101.0, 1.0)]);
+
+        paper.execute(market(Side::Buy, 2.0)).unwrap();
+
+        assert_close(paper.fills()[0].price, 100.5);
+        assert_close(paper.fills()[0].quantity, 2.0);
+    }
+
+    #[test]
+    fn taker_fills_are_capped_by_the_displayed_size() {
+        let mut paper = Paper::new(config(SlippageModel::None));
+        paper.book(&[(99.0, 1.0)], &[(100.0, 1.0), (102.0, 1.0)]);
+
+        paper.execute(market(Side::Buy, 3.0)).unwrap();
+        assert_close(paper.fills()[0].quantity, 2.0);
+        assert!(matches!(
+            paper.reports()[..],
+            [
+                ExecutionKind::Acked,
+                ExecutionKind::Fill(_),
+                ExecutionKind::Cancelled
+            ]
+        ));
+
+        // Limit orders only take the levels within their price and rest the remainder
+        let order = OrderRequest {
+            client_id: "2".to_string(),
+            ..limit(Side::Buy, 101.0, 3.0)
+        };
+        paper.execute(Action::Order(order)).unwrap();
+        assert_close(paper.fills()[1].quantity, 1.0);
+        assert_eq!(paper.open_orders(), 1);
+    }
+
+    #[test]
+    fn fill_or_kill_orders_are_not_filled_partially() {
+        let mut paper = Paper::new(config(SlippageModel::None));
+        paper.trade(100.0, 1.0);
+
+        let order = limit(Side::Buy, 100.0, 2.0).with_time_in_force(TimeInForce::FillOrKill);
+        paper.execute(Action::Order(order)).unwrap();
+
+        assert!(paper.fills().is_empty());
+        assert!(matches!(
+            paper.reports()[..],
+            [ExecutionKind::Acked, ExecutionKind::Cancelled]
+        ));
+    }
+
+    #[test]
+    fn maker_fills_are_capped_by_the_traded_size() {
+        let mut paper = Paper::new(config(SlippageModel::None));
+        paper.trade(100.0, 1.0);
+        paper
+            .execute(Action::Order(limit(Side::Buy, 99.0, 2.0)))
+            .unwrap();
+
+        paper.trade(99.0, 0.5);
+        assert_close(paper.fills()[0].quantity, 0.5);
+        assert_eq!(paper.open_orders(), 1);
+
+        paper.trade(98.0, 5.0);
+        assert_close(paper.fills()[1].quantity, 1.5);
+        assert_close(paper.fills()[1].price, 99.0);
+        assert_eq!(paper.open_orders(), 0);
+    }
+
+    #[test]
+    fn failed_cancels_and_amends_are_rejected() {
+        let mut paper = Paper::new(config(SlippageModel::None));
+
+        assert!(paper.execute(cancel("1")).is_err());
+        assert!(paper.execute(amend(Some(99.0), None)).is_err());
+
+        assert!(matches!(
+            paper.reports()[..],
+            [ExecutionKind::Rejected(_), ExecutionKind::Rejected(_)]
+        ));
+    }
+
+    #[test]
+    fn cancels_close_resting_orders() {
+        let mut paper = Paper::new(config(SlippageModel::None));
+        paper.trade(100.0, 1.0);
+        paper
+            .execute(Action::Order(limit(Side::Buy, 99.0, 1.0)))
+            .unwrap();
+
+        paper.execute(cancel("1")).unwrap();
+
+        assert_eq!(paper.open_orders(), 0);
+        assert!(matches!(
+            paper.reports()[..],
+            [ExecutionKind::Acked, ExecutionKind::Cancelled]
+        ));
+    }
+
+    #[test]
+    fn amends_are_acked_and_validated() {
+        let mut paper = Paper::new(config(SlippageModel::None));
+        paper.trade(100.0, 1.0);
+        paper
+            .execute(Action::Order(limit(Side::Buy, 99.0, 2.0)))
+            .unwrap();
+        paper.trade(99.0, 1.0);
+        paper.reports();
+
+        paper.execute(amend(Some(98.0), Some(3.0))).unwrap();
+        assert!(matches!(paper.reports()[..], [ExecutionKind::Acked]));
+
+        // The quantity is the order total, it has to exceed what already filled
+        assert!(paper.execute(amend(None, Some(1.0))).is_err());
+        assert!(paper.execute(amend(Some(-1.0), None)).is_err());
+        assert!(matches!(
+            paper.reports()[..],
+            [ExecutionKind::Rejected(_), ExecutionKind::Rejected(_)]
+        ));
+
+        paper.trade(98.0, 5.0);
+        assert_close(paper.fills()[1].quantity, 2.0);
+        assert_close(paper.fills()[1].price, 98.0);
+        assert_eq!(paper.open_orders(), 0);
+    }
+
+    #[test]
+    fn amends_crossing_the_market_take_liquidity() {
+        let mut paper = Paper::new(config(SlippageModel::None));
+        paper.book(&[(99.0, 1.0)], &[(100.0, 1.0)]);
+        paper
+            .execute(Action::Order(limit(Side::Buy, 98.0, 2.0)))
+            .unwrap();
+
+        paper.execute(amend(Some(100.0), None)).unwrap();
+
+        let fill = &paper.fills()[0];
+        assert_eq!(fill.liquidity, Liquidity::Taker);
+        assert_close(fill.price, 100.0);
+        assert_close(fill.quantity, 1.0);
+        assert_eq!(paper.open_orders(), 1);
+    }
+
+    #[test]
+    fn post_only_amends_crossing_the_market_are_rejected() {
+        let mut paper = Paper::new(config(SlippageModel::None));
+        paper.book(&[(99.0, 1.0)], &[(100.0, 1.0)]);
+        let order = limit(Side::Buy, 98.0, 1.0).with_order_type(OrderType::PostOnly);
+        paper.execute(Action::Order(order)).unwrap();
+
+        assert!(paper.execute(amend(Some(100.0), None)).is_err());
+
+        assert!(paper.fills().is_empty());
+        assert_eq!(paper.open_orders(), 1);
+        paper.trade(98.0, 1.0);
+        assert_close(paper.fills()[0].price, 98.0);
+    }
+}
@@ -1,7 +1,16 @@
+pub mod backtest;
 pub mod collectors;
 pub mod engines;
 pub mod executors;
 pub mod metrics;
 pub mod models;
+pub mod optimize;
+mod queue;
+pub mod recorders;
+pub mod report;
 pub mod run;
+pub mod schedule;
 pub mod strategies;
+pub mod supervisor;
+#[cfg(test)]
+mod test_support;
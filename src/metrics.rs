@@ -24,6 +24,51 @@ impl BotMetrics {
             "component_errors_total",
             "Total number of errors encountered by each component"
         );
+        describe_counter!(
+            "book_resyncs_total",
+            "Total number of order books invalidated by a sequence gap"
+        );
+        describe_counter!(
+            "collector_reconnects_total",
+            "Total number of reconnect attempts made by each collector"
+        );
+        describe_counter!(
+            "component_restarts_total",
+            "Total number of restarts of each supervised component"
+        );
+        describe_counter!(
+            "channel_lagged_messages_total",
+            "Total number of messages each component missed by falling behind a channel"
+        );
+        describe_gauge!(
+            "channel_queued_messages",
+            "Messages waiting in a channel to be received by each component"
+        );
+        describe_gauge!("channel_capacity", "Capacity of each bounded channel");
+        describe_counter!(
+            "actions_dropped_total",
+            "Total number of actions dropped from each executor queue, by reason"
+        );
+        describe_counter!(
+            "actions_unroutable_total",
+            "Total number of actions from each strategy no executor accepted"
+        );
+        describe_histogram!(
+            "state_request_seconds",
+            "Round trip time of strategy input requests answered by each state engine"
+        );
+        describe_counter!(
+            "state_request_failures_total",
+            "Total number of strategy input requests each state engine did not answer in time"
+        );
+        describe_counter!(
+            "strategy_evaluations_total",
+            "Total number of evaluations of each strategy"
+        );
+        describe_counter!(
+            "strategy_actions_total",
+            "Total number of actions emitted by each strategy"
+        );
 
         // Start Prometheus exporter
         PrometheusBuilder::new()
@@ -65,6 +110,106 @@ impl BotMetrics {
         )
         .increment(1);
     }
+
+    pub fn record_book_resync(engine: &str, source: &str) {
+        counter!(
+            "book_resyncs_total",
+            "engine" => engine.to_string(),
+            "source" => source.to_string(),
+        )
+        .increment(1);
+    }
+
+    pub fn record_reconnect(collector: &str) {
+        counter!(
+            "collector_reconnects_total",
+            "collector" => collector.to_string(),
+        )
+        .increment(1);
+    }
+
+    pub fn record_restart(component: &str) {
+        counter!(
+            "component_restarts_total",
+            "component" => component.to_string(),
+        )
+        .increment(1);
+    }
+
+    pub fn record_lag(channel: &str, component: &str, skipped: u64) {
+        counter!(
+            "channel_lagged_messages_total",
+            "channel" => channel.to_string(),
+            "component" => component.to_string(),
+        )
+        .increment(skipped);
+    }
+
+    pub fn record_channel_queue(channel: &str, component: &str, queued: usize) {
+        gauge!(
+            "channel_queued_messages",
+            "channel" => channel.to_string(),
+            "component" => component.to_string(),
+        )
+        .set(queued as f64);
+    }
+
+    pub fn record_channel_capacity(channel: &str, capacity: usize) {
+        gauge!(
+            "channel_capacity",
+            "channel" => channel.to_string(),
+        )
+        .set(capacity as f64);
+    }
+
+    pub fn record_action_dropped(executor: &str, reason: &str) {
+        counter!(
+            "actions_dropped_total",
+            "executor" => executor.to_string(),
+            "reason" => reason.to_string(),
+        )
+        .increment(1);
+    }
+
+    pub fn record_unroutable_action(strategy: &str, action_type: &str) {
+        counter!(
+            "actions_unroutable_total",
+            "strategy" => strategy.to_string(),
+            "action_type" => action_type.to_string(),
+        )
+        .increment(1);
+    }
+
+    pub fn record_state_request(engine: &str, duration: Duration) {
+        histogram!(
+            "state_request_seconds",
+            "engine" => engine.to_string(),
+        )
+        .record(duration.as_secs_f64());
+    }
+
+    pub fn record_state_request_failure(engine: &str, strategy: &str) {
+        counter!(
+            "state_request_failures_total",
+            "engine" => engine.to_string(),
+            "strategy" => strategy.to_string(),
+        )
+        .increment(1);
+    }
+
+    pub fn record_strategy_evaluation(strategy: &str, actions: usize) {
+        counter!(
+            "strategy_evaluations_total",
+            "strategy" => strategy.to_string(),
+        )
+        .increment(1);
+
+        counter!(
+            "strategy_actions_total",
+            "strategy" => strategy.to_string(),
+        )
+        .increment(actions as u64);
+    }
 }
 
 pub struct DurationRecorder {
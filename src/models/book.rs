@@ -0,0 +1,465 @@
+use std::collections::BTreeMap;
+
+use exstreamer::models::{BinanceDepth, BybitOrderBookData, CoinbaseL2Update, CoinbaseSnapshot};
+use serde::{Deserialize, Serialize};
+
+use crate::models::{
+    event::EventSource,
+    instrument::{Instrument, InstrumentKind, InstrumentRegistry},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Level {
+    pub price: f64,
+    /// Resting size at the price, zero removes the level when applied as a delta
+    pub size: f64,
+}
+
+/// Venue update id range covered by a book update
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Sequence {
+    pub first: u64,
+    pub last: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookUpdate {
+    pub source: EventSource,
+    pub instrument: Instrument,
+    pub bids: Vec<Level>,
+    pub asks: Vec<Level>,
+    /// Present when the venue sequences its depth feed, used for gap detection
+    pub sequence: Option<Sequence>,
+    pub timestamp: u64,
+}
+
+/// Order book key, a source and the instrument quoted on it
+pub type BookKey = (EventSource, Instrument);
+
+/// Local L2 book for a single venue and instrument
+///
+/// Levels are keyed by the bit pattern of the price, which orders the same as the price itself
+/// for the positive, finite values exchanges quote.
+#[derive(Debug, Default)]
+pub struct OrderBook {
+    bids: BTreeMap<u64, f64>,
+    asks: BTreeMap<u64, f64>,
+    last_update_id: Option<u64>,
+    /// Whether a snapshot has been applied since the book was last invalidated
+    synced: bool,
+}
+
+impl OrderBook {
+    pub fn apply_snapshot(&mut self, update: &BookUpdate) {
+        self.bids.clear();
+        self.asks.clear();
+        apply_levels(&mut self.bids, &update.bids);
+        apply_levels(&mut self.asks, &update.asks);
+        self.last_update_id = update.sequence.map(|sequence| sequence.last);
+        self.synced = true;
+    }
+
+    /// Applies a delta, returning an error and invalidating the book on a sequence gap or when
+    /// the delta leaves the book crossed, the only sign of a missed update on feeds without
+    /// sequence numbers
+    pub fn apply_delta(&mut self, update: &BookUpdate) -> anyhow::Result<()> {
+        if !self.synced {
+            return Ok(());
+        }
+
+        if let (Some(last), Some(Sequence { first, last: next })) =
+            (self.last_update_id, update.sequence)
+        {
+            if next <= last {
+                // Stale update already covered by the current book
+                return Ok(());
+            }
+            if first > last + 1 {
+                self.invalidate();
+                return Err(anyhow::anyhow!(
+                    "Sequence gap in {:?} {} book: expected {}, got {}",
+                    update.source,
+                    update.instrument,
+                    last + 1,
+                    first
+                ));
+            }
+        }
+
+        apply_levels(&mut self.bids, &update.bids);
+        apply_levels(&mut self.asks, &update.asks);
+        if let Some(sequence) = update.sequence {
+            self.last_update_id = Some(sequence.last);
+        }
+
+        if let (Some(bid), Some(ask)) = (self.best_bid(), self.best_ask())
+            && bid.price >= ask.price
+        {
+            self.invalidate();
+            return Err(anyhow::anyhow!(
+                "Crossed {:?} {} book: best bid {} at or above best ask {}",
+                update.source,
+                update.instrument,
+                bid.price,
+                ask.price
+            ));
+        }
+        Ok(())
+    }
+
+    /// Drops all levels and ignores deltas until the next snapshot
+    pub fn invalidate(&mut self) {
+        self.bids.clear();
+        self.asks.clear();
+        self.last_update_id = None;
+        self.synced = false;
+    }
+
+    pub fn is_synced(&self) -> bool {
+        self.synced
+    }
+
+    pub fn best_bid(&self) -> Option<Level> {
+        self.bids.iter().next_back().map(to_level)
+    }
+
+    pub fn best_ask(&self) -> Option<Level> {
+        self.asks.iter().next().map(to_level)
+    }
+
+    /// Bid levels from the best price down
+    pub fn bids(&self) -> impl Iterator<Item = Level> + '_ {
+        self.bids.iter().rev().map(to_level)
+    }
+
+    /// Ask levels from the best price up
+    pub fn asks(&self) -> impl Iterator<Item = Level> + '_ {
+        self.asks.iter().map(to_level)
+    }
+
+    /// Total size on each side within `bps` basis points of `mid`
+    pub fn depth_within(&self, mid: f64, bps: f64) -> (f64, f64) {
+        let band = mid * bps / 10_000.0;
+        let bid_size = self
+            .bids
+            .range((mid - band).to_bits()..)
+            .map(|(_, size)| size)
+            .sum();
+        let ask_size = self
+            .asks
+            .range(..=(mid + band).to_bits())
+            .map(|(_, size)| size)
+            .sum();
+        (bid_size, ask_size)
+    }
+}
+
+fn apply_levels(side: &mut BTreeMap<u64, f64>, levels: &[Level]) {
+    for level in levels {
+        if level.size > 0.0 {
+            side.insert(level.price.to_bits(), level.size);
+        } else {
+            side.remove(&level.price.to_bits());
+        }
+    }
+}
+
+fn to_level((price, size): (&u64, &f64)) -> Level {
+    Level {
+        price: f64::from_bits(*price),
+        size: *size,
+    }
+}
+
+/// Depth snapshot served by the Binance REST API, the diff-depth stream only carries changes
+/// and is applied on top of it
+#[derive(Debug, Clone, Deserialize)]
+pub struct BinanceDepthSnapshot {
+    #[serde(rename = "lastUpdateId")]
+    pub last_update_id: u64,
+    pub bids: Vec<(String, String)>,
+    pub asks: Vec<(String, String)>,
+}
+
+/// REST snapshots carry no time, `timestamp` is when the collector received it
+impl TryFrom<(BinanceDepthSnapshot, u64, Instrument)> for BookUpdate {
+    type Error = anyhow::Error;
+
+    fn try_from(
+        (snapshot, timestamp, instrument): (BinanceDepthSnapshot, u64, Instrument),
+    ) -> Result<Self, Self::Error> {
+        Ok(BookUpdate {
+            source: EventSource::Binance,
+            instrument,
+            bids: parse_levels(&snapshot.bids)?,
+            asks: parse_levels(&snapshot.asks)?,
+            sequence: Some(Sequence {
+                first: snapshot.last_update_id,
+                last: snapshot.last_update_id,
+            }),
+            timestamp,
+        })
+    }
+}
+
+/// Diff-depth event covering the update ids `U` to `u`
+impl TryFrom<(BinanceDepth, InstrumentKind, &InstrumentRegistry)> for BookUpdate {
+    type Error = anyhow::Error;
+
+    fn try_from(
+        (depth, kind, registry): (BinanceDepth, InstrumentKind, &InstrumentRegistry),
+    ) -> Result<Self, Self::Error> {
+        let instrument = resolve(registry, EventSource::Binance, kind, &depth.symbol)?;
+
+        Ok(BookUpdate {
+            source: EventSource::Binance,
+            instrument,
+            bids: parse_levels(&depth.bids)?,
+            asks: parse_levels(&depth.asks)?,
+            sequence: Some(Sequence {
+                first: depth.first_update_id,
+                last: depth.last_update_id,
+            }),
+            timestamp: depth.event_time,
+        })
+    }
+}
+
+impl TryFrom<(BybitOrderBookData, u64, InstrumentKind, &InstrumentRegistry)> for BookUpdate {
+    type Error = anyhow::Error;
+
+    fn try_from(
+        (data, timestamp, kind, registry): (
+            BybitOrderBookData,
+            u64,
+            InstrumentKind,
+            &InstrumentRegistry,
+        ),
+    ) -> Result<Self, Self::Error> {
+        let instrument = resolve(registry, EventSource::Bybit, kind, &data.symbol)?;
+
+        Ok(BookUpdate {
+            source: EventSource::Bybit,
+            instrument,
+            bids: parse_levels(&data.bids)?,
+            asks: parse_levels(&data.asks)?,
+            sequence: Some(Sequence {
+                first: data.update_id,
+                last: data.update_id,
+            }),
+            timestamp,
+        })
+    }
+}
+
+/// Snapshots carry no time, `timestamp` is when the collector received it
+impl TryFrom<(CoinbaseSnapshot, u64, InstrumentKind, &InstrumentRegistry)> for BookUpdate {
+    type Error = anyhow::Error;
+
+    fn try_from(
+        (snapshot, timestamp, kind, registry): (
+            CoinbaseSnapshot,
+            u64,
+            InstrumentKind,
+            &InstrumentRegistry,
+        ),
+    ) -> Result<Self, Self::Error> {
+        let instrument = resolve(registry, EventSource::Coinbase, kind, &snapshot.product_id)?;
+
+        Ok(BookUpdate {
+            source: EventSource::Coinbase,
+            instrument,
+            bids: parse_levels(&snapshot.bids)?,
+            asks: parse_levels(&snapshot.asks)?,
+            sequence: None,
+            timestamp,
+        })
+    }
+}
+
+impl TryFrom<(CoinbaseL2Update, InstrumentKind, &InstrumentRegistry)> for BookUpdate {
+    type Error = anyhow::Error;
+
+    fn try_from(
+        (update, kind, registry): (CoinbaseL2Update, InstrumentKind, &InstrumentRegistry),
+    ) -> Result<Self, Self::Error> {
+        let instrument = resolve(registry, EventSource::Coinbase, kind, &update.product_id)?;
+        let timestamp = chrono::DateTime::parse_from_rfc3339(&update.time)?.timestamp_millis();
+
+        let mut bids = Vec::new();
+        let mut asks = Vec::new();
+        for (side, price, size) in &update.changes {
+            let level = parse_level(price, size)?;
+            match side.as_str() {
+                "buy" => bids.push(level),
+                "sell" => asks.push(level),
+                _ => return Err(anyhow::anyhow!("Unknown Coinbase book side: '{}'", side)),
+            }
+        }
+
+        Ok(BookUpdate {
+            source: EventSource::Coinbase,
+            instrument,
+            bids,
+            asks,
+            sequence: None,
+            timestamp: timestamp as u64,
+        })
+    }
+}
+
+fn resolve(
+    registry: &InstrumentRegistry,
+    source: EventSource,
+    kind: InstrumentKind,
+    symbol: &str,
+) -> anyhow::Result<Instrument> {
+    registry
+        .resolve(&source, kind, symbol)
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("Unknown {:?} {:?} symbol: '{}'", source, kind, symbol))
+}
+
+fn parse_levels(levels: &[(String, String)]) -> anyhow::Result<Vec<Level>> {
+    levels
+        .iter()
+        .map(|(price, size)| parse_level(price, size))
+        .collect()
+}
+
+fn parse_level(price: &str, size: &str) -> anyhow::Result<Level> {
+    match (price.parse::<f64>(), size.parse::<f64>()) {
+        (Ok(price), Ok(size)) => Ok(Level { price, size }),
+        _ => Err(anyhow::anyhow!(
+            "Failed to parse book level from string to f64: price='{}', size='{}'",
+            price,
+            size
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support as support;
+
+    fn update(
+        source: EventSource,
+        bids: &[(f64, f64)],
+        asks: &[(f64, f64)],
+        sequence: Option<(u64, u64)>,
+    ) -> BookUpdate {
+        let levels = |levels: &[(f64, f64)]| {
+            levels
+                .iter()
+                .map(|&(price, size)| Level { price, size })
+                .collect()
+        };
+        BookUpdate {
+            source,
+            instrument: support::instrument(),
+            bids: levels(bids),
+            asks: levels(asks),
+            sequence: sequence.map(|(first, last)| Sequence { first, last }),
+            timestamp: 0,
+        }
+    }
+
+    fn synced_book() -> OrderBook {
+        let mut book = OrderBook::default();
+        book.apply_snapshot(&update(
+            EventSource::Binance,
+            &[(99.0, 1.0)],
+            &[(101.0, 1.0)],
+            Some((10, 10)),
+        ));
+        book
+    }
+
+    #[test]
+    fn stale_deltas_are_skipped() {
+        let mut book = synced_book();
+
+        book.apply_delta(&update(
+            EventSource::Binance,
+            &[(100.0, 5.0)],
+            &[],
+            Some((8, 10)),
+        ))
+        .unwrap();
+
+        assert!(book.is_synced());
+        assert_eq!(book.best_bid().unwrap().price, 99.0);
+    }
+
+    #[test]
+    fn deltas_overlapping_the_book_apply() {
+        let mut book = synced_book();
+
+        book.apply_delta(&update(
+            EventSource::Binance,
+            &[(100.0, 5.0), (99.0, 0.0)],
+            &[],
+            Some((9, 11)),
+        ))
+        .unwrap();
+
+        assert_eq!(
+            book.bids().collect::<Vec<_>>(),
+            vec![Level {
+                price: 100.0,
+                size: 5.0
+            }]
+        );
+        book.apply_delta(&update(
+            EventSource::Binance,
+            &[],
+            &[(102.0, 1.0)],
+            Some((12, 12)),
+        ))
+        .unwrap();
+        assert_eq!(book.asks().count(), 2);
+    }
+
+    #[test]
+    fn sequence_gaps_invalidate_the_book() {
+        let mut book = synced_book();
+
+        let result = book.apply_delta(&update(
+            EventSource::Binance,
+            &[(100.0, 5.0)],
+            &[],
+            Some((12, 13)),
+        ));
+
+        assert!(result.is_err());
+        assert!(!book.is_synced());
+        assert!(book.best_bid().is_none());
+        // Deltas are ignored until the next snapshot
+        book.apply_delta(&update(
+            EventSource::Binance,
+            &[(100.0, 5.0)],
+            &[],
+            Some((14, 14)),
+        ))
+        .unwrap();
+        assert!(book.best_bid().is_none());
+    }
+
+    #[test]
+    fn crossed_books_are_invalidated() {
+        let mut book = OrderBook::default();
+        book.apply_snapshot(&update(
+            EventSource::Coinbase,
+            &[(99.0, 1.0)],
+            &[(101.0, 1.0)],
+            None,
+        ));
+
+        let result = book.apply_delta(&update(EventSource::Coinbase, &[(101.0, 1.0)], &[], None));
+
+        assert!(result.is_err());
+        assert!(!book.is_synced());
+        assert!(book.best_ask().is_none());
+    }
+}
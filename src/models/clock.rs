@@ -0,0 +1,111 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use tokio::sync::watch;
+
+/// Source of the current time for strategy scheduling and time-dependent state
+///
+/// Processing durations recorded in metrics always use wall time.
+#[async_trait::async_trait]
+pub trait Clock: Send + Sync + std::fmt::Debug {
+    /// Milliseconds since the Unix epoch
+    fn now_ms(&self) -> u64;
+
+    /// Resolves once the clock reaches `timestamp`
+    async fn sleep_until(&self, timestamp: u64);
+
+    /// Called by the bot with the timestamp of every collected event
+    fn observe(&self, _timestamp: u64) {}
+}
+
+pub type SharedClock = Arc<dyn Clock>;
+
+/// Wall clock time, used for live trading
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LiveClock;
+
+#[async_trait::async_trait]
+impl Clock for LiveClock {
+    fn now_ms(&self) -> u64 {
+        chrono::Utc::now().timestamp_millis() as u64
+    }
+
+    async fn sleep_until(&self, timestamp: u64) {
+        let remaining = timestamp.saturating_sub(self.now_ms());
+        tokio::time::sleep(Duration::from_millis(remaining)).await;
+    }
+}
+
+/// Clock following event time, for replays, backtests and deterministic tests
+///
+/// Time only moves forward, when events are observed or the clock is advanced explicitly.
+#[derive(Debug)]
+pub struct SimulatedClock {
+    now: watch::Sender<u64>,
+    /// Whether sleeping advances the clock to the wake up time instead of waiting for it
+    auto_advance: AtomicBool,
+}
+
+impl SimulatedClock {
+    pub fn new(start: u64) -> Self {
+        Self {
+            now: watch::Sender::new(start),
+            auto_advance: AtomicBool::new(false),
+        }
+    }
+
+    /// Moves the clock to `timestamp`, ignoring timestamps in the past
+    pub fn advance_to(&self, timestamp: u64) {
+        self.now.send_if_modified(|now| {
+            if timestamp > *now {
+                *now = timestamp;
+                true
+            } else {
+                false
+            }
+        });
+    }
+
+    pub fn advance_by(&self, ms: u64) {
+        self.advance_to(self.now_ms() + ms);
+    }
+
+    /// Lets sleepers advance the clock themselves, required when nothing else moves the clock
+    /// while a component sleeps, as in sequential backtests
+    pub fn set_auto_advance(&self, enabled: bool) {
+        self.auto_advance.store(enabled, Ordering::Relaxed);
+    }
+}
+
+impl Default for SimulatedClock {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+#[async_trait::async_trait]
+impl Clock for SimulatedClock {
+    fn now_ms(&self) -> u64 {
+        *self.now.borrow()
+    }
+
+    async fn sleep_until(&self, timestamp: u64) {
+        if self.auto_advance.load(Ordering::Relaxed) {
+            self.advance_to(timestamp);
+            return;
+        }
+
+        let mut now = self.now.subscribe();
+        // The sender lives as long as the clock, so waiting cannot fail
+        let _ = now.wait_for(|now| *now >= timestamp).await;
+    }
+
+    fn observe(&self, timestamp: u64) {
+        self.advance_to(timestamp);
+    }
+}
@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Clone, Error, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CollectorError {
+    #[error("Failed to connect: {0}")]
+    Connect(String),
+    #[error("Subscription rejected: {0}")]
+    SubscriptionRejected(String),
+    #[error("Failed to decode message: {0}")]
+    Decode(String),
+    #[error("Stream closed")]
+    StreamClosed,
+}
@@ -1,29 +1,108 @@
-use crate::models::trade::Trade;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+use crate::models::{
+    book::BookUpdate, error::CollectorError, order::ExecutionReport, trade::Trade,
+    traits::Timestamped, trigger::TriggerEvent,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum InternalEvent {
     Trade(Trade),
+    /// Full book replacing any local state for the instrument
+    BookSnapshot(BookUpdate),
+    /// Incremental level changes applied on top of the last snapshot
+    BookDelta(BookUpdate),
+    Connection(EventSource, ConnectionStatus),
+    /// Asks the collector of the source to resubscribe, published by state engines that lost
+    /// track of the source, e.g. after a gap in its depth feed
+    Resync(EventSource),
+    ExecutionReport(ExecutionReport),
     Error(String),
     Unsupported(String),
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConnectionStatus {
+    Disconnected(CollectorError),
+    Reconnected,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum EventSource {
     Binance,
     Bybit,
     Coinbase,
 }
 
+/// Messages a collector received but could not decode are published as errors, the stream
+/// carries on with the next message
+impl From<CollectorError> for InternalEvent {
+    fn from(error: CollectorError) -> Self {
+        InternalEvent::Error(error.to_string())
+    }
+}
+
 impl InternalEvent {
     pub fn event_type(&self) -> String {
         match self {
             InternalEvent::Trade(_) => "Trade".to_string(),
+            InternalEvent::BookSnapshot(_) => "BookSnapshot".to_string(),
+            InternalEvent::BookDelta(_) => "BookDelta".to_string(),
+            InternalEvent::Connection(..) => "Connection".to_string(),
+            InternalEvent::Resync(_) => "Resync".to_string(),
+            InternalEvent::ExecutionReport(_) => "ExecutionReport".to_string(),
             InternalEvent::Error(_) => "Error".to_string(),
             InternalEvent::Unsupported(_) => "Unsupported".to_string(),
         }
     }
 }
 
+impl Timestamped for InternalEvent {
+    fn timestamp(&self) -> Option<u64> {
+        match self {
+            InternalEvent::Trade(trade) => Some(trade.timestamp),
+            InternalEvent::BookSnapshot(update) | InternalEvent::BookDelta(update) => {
+                Some(update.timestamp)
+            }
+            InternalEvent::ExecutionReport(report) => Some(report.timestamp),
+            _ => None,
+        }
+    }
+}
+
+impl TriggerEvent for InternalEvent {
+    fn event_type(&self) -> String {
+        InternalEvent::event_type(self)
+    }
+
+    fn source(&self) -> Option<&EventSource> {
+        match self {
+            InternalEvent::Trade(trade) => Some(&trade.source),
+            InternalEvent::BookSnapshot(update) | InternalEvent::BookDelta(update) => {
+                Some(&update.source)
+            }
+            InternalEvent::Connection(source, _) | InternalEvent::Resync(source) => Some(source),
+            InternalEvent::ExecutionReport(report) => Some(&report.source),
+            _ => None,
+        }
+    }
+
+    fn trade(&self) -> Option<&Trade> {
+        match self {
+            InternalEvent::Trade(trade) => Some(trade),
+            _ => None,
+        }
+    }
+
+    fn book_update(&self) -> Option<(&BookUpdate, bool)> {
+        match self {
+            InternalEvent::BookSnapshot(update) => Some((update, true)),
+            InternalEvent::BookDelta(update) => Some((update, false)),
+            _ => None,
+        }
+    }
+}
+
 impl EventSource {
     pub fn get_all() -> Vec<EventSource> {
         vec![
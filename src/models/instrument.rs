@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::{event::EventSource, trade::normalize_symbol};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InstrumentKind {
+    Spot,
+    Perp,
+    Future,
+}
+
+/// A tradable market on a venue, identified canonically by base, quote and kind
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Instrument {
+    pub base: String,
+    pub quote: String,
+    pub kind: InstrumentKind,
+    /// Venue-native symbol used for subscriptions, e.g. `btcusdt` or `BTC-USD`
+    pub symbol: String,
+}
+
+impl std::fmt::Display for Instrument {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.canonical())
+    }
+}
+
+impl Instrument {
+    pub fn new(base: &str, quote: &str, kind: InstrumentKind, symbol: &str) -> Self {
+        Self {
+            base: base.to_ascii_uppercase(),
+            quote: quote.to_ascii_uppercase(),
+            kind,
+            symbol: symbol.to_string(),
+        }
+    }
+
+    /// Builds an instrument from a canonical `BASE/QUOTE` name
+    pub fn from_canonical(
+        canonical: &str,
+        kind: InstrumentKind,
+        symbol: &str,
+    ) -> anyhow::Result<Self> {
+        match canonical.split_once('/') {
+            Some((base, quote)) if !base.is_empty() && !quote.is_empty() => {
+                Ok(Self::new(base, quote, kind, symbol))
+            }
+            _ => Err(anyhow::anyhow!(
+                "Invalid canonical instrument name '{}', expected BASE/QUOTE",
+                canonical
+            )),
+        }
+    }
+
+    /// Canonical venue-independent name, e.g. `BTC/USD`
+    pub fn canonical(&self) -> String {
+        format!("{}/{}", self.base, self.quote)
+    }
+
+    /// Whether both instruments refer to the same market regardless of venue
+    pub fn same_market(&self, other: &Instrument) -> bool {
+        self.base == other.base && self.quote == other.quote && self.kind == other.kind
+    }
+}
+
+/// Registry key, venues reuse the same symbol across market categories, e.g. Bybit lists the
+/// spot and perpetual `BTCUSDT` markets under one symbol
+type RegistryKey = (EventSource, InstrumentKind, String);
+
+/// Maps between canonical instrument names and venue-native symbols
+#[derive(Debug, Clone, Default)]
+pub struct InstrumentRegistry {
+    instruments: HashMap<RegistryKey, Instrument>,
+}
+
+impl InstrumentRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, source: EventSource, instrument: Instrument) {
+        let key = (
+            source,
+            instrument.kind,
+            normalize_symbol(&instrument.symbol),
+        );
+        self.instruments.insert(key, instrument);
+    }
+
+    pub fn register(
+        &mut self,
+        source: EventSource,
+        canonical: &str,
+        kind: InstrumentKind,
+        symbol: &str,
+    ) -> anyhow::Result<()> {
+        let instrument = Instrument::from_canonical(canonical, kind, symbol)?;
+        self.insert(source, instrument);
+        Ok(())
+    }
+
+    /// Resolves a venue-native symbol of a market category, in any casing or separator style, to
+    /// its instrument
+    pub fn resolve(
+        &self,
+        source: &EventSource,
+        kind: InstrumentKind,
+        symbol: &str,
+    ) -> Option<&Instrument> {
+        self.instruments
+            .get(&(source.clone(), kind, normalize_symbol(symbol)))
+    }
+
+    /// Looks up the venue-native symbol of a canonical instrument name
+    pub fn native_symbol(
+        &self,
+        source: &EventSource,
+        canonical: &str,
+        kind: InstrumentKind,
+    ) -> Option<&str> {
+        let canonical = canonical.to_ascii_uppercase();
+        self.instruments
+            .iter()
+            .find(|((s, k, _), instrument)| {
+                s == source && *k == kind && instrument.canonical() == canonical
+            })
+            .map(|(_, instrument)| instrument.symbol.as_str())
+    }
+
+    /// All instruments registered for a source
+    pub fn instruments(&self, source: &EventSource) -> impl Iterator<Item = &Instrument> {
+        self.instruments
+            .iter()
+            .filter(move |((s, _, _), _)| s == source)
+            .map(|(_, instrument)| instrument)
+    }
+}
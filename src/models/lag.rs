@@ -0,0 +1,11 @@
+/// How a state engine recovers after falling behind the event bus and missing events
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LagPolicy {
+    /// Carry on with the events still on the bus, for state that heals with new events
+    #[default]
+    Skip,
+    /// Rebuild the state with `sync_state` before processing further events
+    Resync,
+    /// Fail the engine, leaving recovery to its supervision
+    Fail,
+}
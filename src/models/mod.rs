@@ -1,9 +1,33 @@
+pub mod book;
+pub mod clock;
+pub mod error;
 pub mod event;
+pub mod instrument;
+pub mod lag;
+pub mod order;
 pub mod output;
+pub mod queue;
+pub mod reconnect;
+pub mod record;
+pub mod route;
+pub mod strategy;
 pub mod trade;
 pub mod traits;
+pub mod trigger;
 
+pub use book::*;
+pub use clock::*;
+pub use error::*;
 pub use event::*;
+pub use instrument::*;
+pub use lag::*;
+pub use order::*;
 pub use output::*;
+pub use queue::*;
+pub use reconnect::*;
+pub use record::*;
+pub use route::*;
+pub use strategy::*;
 pub use trade::*;
 pub use traits::*;
+pub use trigger::*;
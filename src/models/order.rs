@@ -0,0 +1,335 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::{
+    event::EventSource, instrument::Instrument, route::Routable, traits::Attributed,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+impl Side {
+    /// `1.0` for buys and `-1.0` for sells, for signed quantities
+    pub fn sign(&self) -> f64 {
+        match self {
+            Side::Buy => 1.0,
+            Side::Sell => -1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderType {
+    Market,
+    Limit,
+    /// Limit order rejected instead of taking liquidity
+    PostOnly,
+    /// Limit order whose unfilled remainder is cancelled immediately
+    Ioc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeInForce {
+    GoodTilCancelled,
+    ImmediateOrCancel,
+    FillOrKill,
+}
+
+#[derive(Debug, Clone)]
+pub struct OrderRequest {
+    /// Strategy-assigned id used to cancel, amend and reconcile the order
+    pub client_id: String,
+    /// Venue the order is routed to
+    pub source: EventSource,
+    pub instrument: Instrument,
+    pub side: Side,
+    pub order_type: OrderType,
+    /// Limit price, ignored for market orders
+    pub price: Option<f64>,
+    pub quantity: f64,
+    pub time_in_force: TimeInForce,
+    /// Strategy that emitted the order, set by the bot
+    pub strategy_id: Option<String>,
+}
+
+impl OrderRequest {
+    pub fn market(
+        client_id: &str,
+        source: EventSource,
+        instrument: Instrument,
+        side: Side,
+        quantity: f64,
+    ) -> Self {
+        Self {
+            client_id: client_id.to_string(),
+            source,
+            instrument,
+            side,
+            order_type: OrderType::Market,
+            price: None,
+            quantity,
+            time_in_force: TimeInForce::ImmediateOrCancel,
+            strategy_id: None,
+        }
+    }
+
+    pub fn limit(
+        client_id: &str,
+        source: EventSource,
+        instrument: Instrument,
+        side: Side,
+        price: f64,
+        quantity: f64,
+    ) -> Self {
+        Self {
+            client_id: client_id.to_string(),
+            source,
+            instrument,
+            side,
+            order_type: OrderType::Limit,
+            price: Some(price),
+            quantity,
+            time_in_force: TimeInForce::GoodTilCancelled,
+            strategy_id: None,
+        }
+    }
+
+    pub fn with_order_type(mut self, order_type: OrderType) -> Self {
+        self.order_type = order_type;
+        self
+    }
+
+    pub fn with_time_in_force(mut self, time_in_force: TimeInForce) -> Self {
+        self.time_in_force = time_in_force;
+        self
+    }
+
+    /// Whether any unfilled quantity must be cancelled rather than left resting
+    pub fn is_immediate(&self) -> bool {
+        self.order_type == OrderType::Market
+            || self.order_type == OrderType::Ioc
+            || self.time_in_force != TimeInForce::GoodTilCancelled
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CancelRequest {
+    pub client_id: String,
+    pub source: EventSource,
+    pub instrument: Instrument,
+    /// Strategy that emitted the cancel, set by the bot
+    pub strategy_id: Option<String>,
+}
+
+/// Replaces the price and/or quantity of a resting order
+#[derive(Debug, Clone)]
+pub struct AmendRequest {
+    pub client_id: String,
+    pub source: EventSource,
+    pub instrument: Instrument,
+    pub price: Option<f64>,
+    pub quantity: Option<f64>,
+    /// Strategy that emitted the amend, set by the bot
+    pub strategy_id: Option<String>,
+}
+
+/// Action emitted by strategies and handled by executors
+#[derive(Debug, Clone)]
+pub enum Action {
+    Order(OrderRequest),
+    Cancel(CancelRequest),
+    Amend(AmendRequest),
+}
+
+impl Action {
+    pub fn client_id(&self) -> &str {
+        match self {
+            Action::Order(order) => &order.client_id,
+            Action::Cancel(cancel) => &cancel.client_id,
+            Action::Amend(amend) => &amend.client_id,
+        }
+    }
+
+    pub fn source(&self) -> &EventSource {
+        match self {
+            Action::Order(order) => &order.source,
+            Action::Cancel(cancel) => &cancel.source,
+            Action::Amend(amend) => &amend.source,
+        }
+    }
+
+    pub fn instrument(&self) -> &Instrument {
+        match self {
+            Action::Order(order) => &order.instrument,
+            Action::Cancel(cancel) => &cancel.instrument,
+            Action::Amend(amend) => &amend.instrument,
+        }
+    }
+
+    /// Key under which a queued action can be replaced, only by an action of the same kind for
+    /// the same order, e.g. a newer amend. Orders without a client id are never replaced
+    pub fn coalesce_key(&self) -> Option<String> {
+        if self.client_id().is_empty() {
+            return None;
+        }
+        Some(format!(
+            "{:?} {} {}",
+            self.source(),
+            self.action_type(),
+            self.client_id()
+        ))
+    }
+
+    /// Key of the order the action places or acts on, orders without a client id have none
+    pub fn order_key(&self) -> Option<String> {
+        if self.client_id().is_empty() {
+            return None;
+        }
+        Some(format!("{:?} {}", self.source(), self.client_id()))
+    }
+}
+
+impl Attributed for Action {
+    fn strategy_id(&self) -> Option<&str> {
+        match self {
+            Action::Order(order) => order.strategy_id.as_deref(),
+            Action::Cancel(cancel) => cancel.strategy_id.as_deref(),
+            Action::Amend(amend) => amend.strategy_id.as_deref(),
+        }
+    }
+
+    fn set_strategy_id(&mut self, strategy_id: &str) {
+        let slot = match self {
+            Action::Order(order) => &mut order.strategy_id,
+            Action::Cancel(cancel) => &mut cancel.strategy_id,
+            Action::Amend(amend) => &mut amend.strategy_id,
+        };
+        *slot = Some(strategy_id.to_string());
+    }
+}
+
+impl Routable for Action {
+    fn action_type(&self) -> String {
+        match self {
+            Action::Order(_) => "Order".to_string(),
+            Action::Cancel(_) => "Cancel".to_string(),
+            Action::Amend(_) => "Amend".to_string(),
+        }
+    }
+
+    fn venue(&self) -> Option<&EventSource> {
+        Some(self.source())
+    }
+
+    fn instrument(&self) -> Option<&Instrument> {
+        Some(Action::instrument(self))
+    }
+}
+
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Action::Order(order) => write!(
+                f,
+                "Order {} {:?} {}: {:?} {:?} {} @ {:?}",
+                order.client_id,
+                order.source,
+                order.instrument,
+                order.side,
+                order.order_type,
+                order.quantity,
+                order.price
+            ),
+            Action::Cancel(cancel) => write!(
+                f,
+                "Cancel {} {:?} {}",
+                cancel.client_id, cancel.source, cancel.instrument
+            ),
+            Action::Amend(amend) => write!(
+                f,
+                "Amend {} {:?} {}: {:?} @ {:?}",
+                amend.client_id, amend.source, amend.instrument, amend.quantity, amend.price
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Liquidity {
+    Maker,
+    Taker,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fill {
+    pub client_id: String,
+    pub source: EventSource,
+    pub instrument: Instrument,
+    pub side: Side,
+    pub price: f64,
+    pub quantity: f64,
+    /// Fee paid in the quote asset
+    pub fee: f64,
+    pub liquidity: Liquidity,
+    pub timestamp: u64,
+    /// Strategy whose order was filled, absent from recordings made before attribution
+    #[serde(default)]
+    pub strategy_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderStatus {
+    New,
+    Acked,
+    PartiallyFilled,
+    Filled,
+    Cancelled,
+    Rejected,
+}
+
+impl OrderStatus {
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            OrderStatus::Filled | OrderStatus::Cancelled | OrderStatus::Rejected
+        )
+    }
+
+    /// Whether the lifecycle allows moving from this status to `next`
+    ///
+    /// Fills may arrive before the acknowledgement, so they are accepted from `New`.
+    pub fn can_transition_to(&self, next: OrderStatus) -> bool {
+        match self {
+            OrderStatus::New => next != OrderStatus::New,
+            OrderStatus::Acked => matches!(
+                next,
+                OrderStatus::PartiallyFilled | OrderStatus::Filled | OrderStatus::Cancelled
+            ),
+            OrderStatus::PartiallyFilled => matches!(
+                next,
+                OrderStatus::PartiallyFilled | OrderStatus::Filled | OrderStatus::Cancelled
+            ),
+            OrderStatus::Filled | OrderStatus::Cancelled | OrderStatus::Rejected => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ExecutionKind {
+    Acked,
+    Fill(Fill),
+    Cancelled,
+    Rejected(String),
+}
+
+/// Outcome of an action as reported by the executor or venue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionReport {
+    pub client_id: String,
+    pub source: EventSource,
+    pub instrument: Instrument,
+    pub kind: ExecutionKind,
+    pub timestamp: u64,
+}
@@ -1,13 +1,25 @@
-use crate::models::event::EventSource;
+use std::collections::HashMap;
+
+use crate::models::{
+    event::EventSource,
+    instrument::{Instrument, InstrumentKind},
+    order::{Fill, OrderRequest, OrderStatus},
+};
 
 #[derive(Debug)]
 pub enum StateOutput {
     Prices(Vec<PriceData>),
+    Books(Vec<BookData>),
+    FairPrices(Vec<FairPriceData>),
+    Portfolio(PortfolioData),
+    Orders(OrdersData),
+    Performance(PerformanceData),
 }
 
 #[derive(Debug)]
 pub struct PriceData {
     pub source: EventSource,
+    pub instrument: Instrument,
     pub price: f64,
     pub rsi: f64,
     pub natr: f64,
@@ -24,9 +36,16 @@ impl std::fmt::Display for PriceData {
 }
 
 impl PriceData {
-    pub fn new(source: EventSource, price: f64, rsi: f64, natr: f64) -> Self {
+    pub fn new(
+        source: EventSource,
+        instrument: Instrument,
+        price: f64,
+        rsi: f64,
+        natr: f64,
+    ) -> Self {
         Self {
             source,
+            instrument,
             price,
             rsi,
             natr,
@@ -35,6 +54,7 @@ impl PriceData {
 
     pub fn try_new(
         source: EventSource,
+        instrument: Instrument,
         price: Option<f64>,
         rsi: Option<f64>,
         natr: Option<f64>,
@@ -42,6 +62,7 @@ impl PriceData {
         match (price, rsi, natr) {
             (Some(price), Some(rsi), Some(natr)) => Some(Self {
                 source,
+                instrument,
                 price,
                 rsi,
                 natr,
@@ -50,3 +71,100 @@ impl PriceData {
         }
     }
 }
+
+#[derive(Debug)]
+pub struct BookData {
+    pub source: EventSource,
+    pub instrument: Instrument,
+    pub best_bid: f64,
+    pub best_ask: f64,
+    pub mid: f64,
+    pub spread: f64,
+    pub depth: Vec<DepthData>,
+}
+
+/// Resting size on each side within `bps` basis points of the mid
+#[derive(Debug)]
+pub struct DepthData {
+    pub bps: f64,
+    pub bid_size: f64,
+    pub ask_size: f64,
+}
+
+impl std::fmt::Display for BookData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Bid: {}, Ask: {}, Mid: {}, Spread: {}",
+            self.best_bid, self.best_ask, self.mid, self.spread
+        )
+    }
+}
+
+/// Fair value of a market combined across venues
+#[derive(Debug)]
+pub struct FairPriceData {
+    /// Canonical market name, e.g. `BTC/USD`
+    pub canonical: String,
+    pub kind: InstrumentKind,
+    pub price: f64,
+    /// Weighted standard deviation of venue prices around the fair price, in basis points
+    pub dispersion_bps: f64,
+    /// Fraction of known venues that contributed, after dropping stale and outlier venues
+    pub confidence: f64,
+    pub sources: Vec<EventSource>,
+}
+
+impl std::fmt::Display for FairPriceData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Fair: {}, Dispersion: {}bps, Confidence: {}",
+            self.price, self.dispersion_bps, self.confidence
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct PortfolioData {
+    pub balances: HashMap<String, f64>,
+    pub positions: Vec<PositionData>,
+    pub open_orders: usize,
+}
+
+#[derive(Debug)]
+pub struct PositionData {
+    pub source: EventSource,
+    pub instrument: Instrument,
+    /// Signed quantity, negative when short
+    pub quantity: f64,
+    pub avg_price: f64,
+    pub realized_pnl: f64,
+}
+
+#[derive(Debug)]
+pub struct OrdersData {
+    /// Orders that have not reached a terminal status
+    pub open: Vec<OrderData>,
+    /// Most recent fills, oldest first
+    pub fills: Vec<Fill>,
+}
+
+#[derive(Debug, Clone)]
+pub struct OrderData {
+    pub request: OrderRequest,
+    pub status: OrderStatus,
+    pub filled_quantity: f64,
+    pub avg_fill_price: f64,
+}
+
+/// Running account performance, all amounts in the quote asset
+#[derive(Debug, Clone)]
+pub struct PerformanceData {
+    pub equity: f64,
+    pub realized_pnl: f64,
+    pub unrealized_pnl: f64,
+    pub fees: f64,
+    /// Current decline from the equity peak, as a fraction of the peak
+    pub drawdown: f64,
+}
@@ -0,0 +1,35 @@
+/// What happens to an action pushed onto a full executor queue
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Drop the oldest queued action to make room, along with the queued and pushed actions that
+    /// depend on it
+    #[default]
+    DropOldest,
+    /// Drop the pushed action
+    DropNewest,
+    /// Make the strategy wait until the executor frees a slot
+    Block,
+    /// Replace the queued action with the same coalesce key, dropping the oldest action if there
+    /// is none and the queue is full
+    Coalesce,
+}
+
+/// Queue of actions waiting for an executor
+#[derive(Debug, Clone)]
+pub struct QueuePolicy {
+    /// Actions queued before the overflow policy applies, at least one
+    pub capacity: usize,
+    pub overflow: OverflowPolicy,
+    /// Actions executed concurrently, actions with the same key always execute in order
+    pub max_in_flight: usize,
+}
+
+impl Default for QueuePolicy {
+    fn default() -> Self {
+        Self {
+            capacity: 1_024,
+            overflow: OverflowPolicy::default(),
+            max_in_flight: 1,
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use std::time::Duration;
+
+/// Exponential backoff with jitter used when a collector stream fails or ends.
+#[derive(Debug, Clone)]
+pub struct ReconnectPolicy {
+    pub initial_delay_ms: u64,
+    pub max_delay_ms: u64,
+    pub multiplier: f64,
+    /// Fraction of the delay randomly added or removed, e.g. `0.2` for ±20%
+    pub jitter: f64,
+    /// Maximum consecutive failed attempts before giving up, `None` retries forever
+    pub max_retries: Option<u32>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay_ms: 500,
+            max_delay_ms: 30_000,
+            multiplier: 2.0,
+            jitter: 0.2,
+            max_retries: None,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let base = self.initial_delay_ms as f64 * self.multiplier.powi(attempt as i32);
+        let capped = base.min(self.max_delay_ms as f64);
+        let jitter = if self.jitter > 0.0 {
+            capped * rand::random_range(-self.jitter..=self.jitter)
+        } else {
+            0.0
+        };
+
+        Duration::from_millis((capped + jitter).max(0.0) as u64)
+    }
+
+    pub fn should_retry(&self, attempt: u32) -> bool {
+        self.max_retries.is_none_or(|max| attempt < max)
+    }
+}
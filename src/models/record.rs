@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+/// An event as captured from the bus, the unit stored in recording files
+///
+/// Recordings are JSON Lines files, optionally gzip compressed (`.jsonl.gz`), holding one
+/// `RecordedEvent` per line in the order events were received, e.g.
+///
+/// ```text
+/// {"received_at":1718000000123,"event":{"Trade":{"source":"Binance",...}}}
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedEvent<E> {
+    /// Milliseconds since the Unix epoch at which the event was received from the bus
+    pub received_at: u64,
+    pub event: E,
+}
+
+impl<E> RecordedEvent<E> {
+    pub fn new(received_at: u64, event: E) -> Self {
+        Self { received_at, event }
+    }
+}
@@ -0,0 +1,81 @@
+use crate::models::{event::EventSource, instrument::Instrument, traits::Attributed};
+
+/// Actions the bot can route to executors
+pub trait Routable: Attributed {
+    /// Kind of the action, e.g. `"Order"`
+    fn action_type(&self) -> String;
+
+    /// Venue the action is meant for, `None` for actions bound to no venue
+    fn venue(&self) -> Option<&EventSource> {
+        None
+    }
+
+    fn instrument(&self) -> Option<&Instrument> {
+        None
+    }
+}
+
+/// Plain text actions, as emitted by the echo strategy, are bound to no venue
+impl Routable for String {
+    fn action_type(&self) -> String {
+        "Text".to_string()
+    }
+}
+
+/// Actions an executor receives, each list left empty accepts any value
+///
+/// An action must match every non-empty list, e.g. a filter with a venue and two instruments
+/// accepts actions for either instrument on that venue.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ActionFilter {
+    pub venues: Vec<EventSource>,
+    pub instruments: Vec<Instrument>,
+    pub action_types: Vec<String>,
+    pub strategies: Vec<String>,
+}
+
+impl ActionFilter {
+    /// Filter accepting every action
+    pub fn any() -> Self {
+        Self::default()
+    }
+
+    pub fn with_venue(mut self, venue: EventSource) -> Self {
+        self.venues.push(venue);
+        self
+    }
+
+    pub fn with_instrument(mut self, instrument: Instrument) -> Self {
+        self.instruments.push(instrument);
+        self
+    }
+
+    pub fn with_action_type(mut self, action_type: &str) -> Self {
+        self.action_types.push(action_type.to_string());
+        self
+    }
+
+    pub fn with_strategy(mut self, strategy_id: &str) -> Self {
+        self.strategies.push(strategy_id.to_string());
+        self
+    }
+
+    pub fn matches<A: Routable>(&self, action: &A) -> bool {
+        let venue = self.venues.is_empty()
+            || action
+                .venue()
+                .is_some_and(|venue| self.venues.contains(venue));
+        let instrument = self.instruments.is_empty()
+            || action
+                .instrument()
+                .is_some_and(|instrument| self.instruments.contains(instrument));
+        let action_type =
+            self.action_types.is_empty() || self.action_types.contains(&action.action_type());
+        let strategy = self.strategies.is_empty()
+            || action
+                .strategy_id()
+                .is_some_and(|id| self.strategies.iter().any(|s| s == id));
+
+        venue && instrument && action_type && strategy
+    }
+}
@@ -0,0 +1,152 @@
+use std::marker::PhantomData;
+
+use anyhow::Result;
+
+use crate::models::{
+    traits::{InputBuilder, Strategy},
+    trigger::Trigger,
+};
+
+/// What the bot does when state engines fail to respond to a request for a strategy input
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InputPolicy {
+    /// Skip the evaluation until every state engine responds
+    #[default]
+    Complete,
+    /// Build the input from the engines that responded, telling the input builder which are
+    /// missing
+    Partial,
+}
+
+/// Strategy registered with a bot under an id
+///
+/// The input type is erased, so strategies with different inputs share the same state engines
+/// and executors. The id tags every action the strategy emits and labels its metrics, so it
+/// must be unique within a bot.
+pub struct RegisteredStrategy<D, A> {
+    id: String,
+    strategy: Box<dyn ErasedStrategy<D, A>>,
+}
+
+impl<D, A> RegisteredStrategy<D, A> {
+    pub fn new<S, I>(id: &str, strategy: S) -> Self
+    where
+        S: Strategy<D, I, A> + 'static,
+        D: 'static,
+        I: 'static,
+        A: 'static,
+    {
+        Self {
+            id: id.to_string(),
+            strategy: Box::new(Typed {
+                strategy,
+                input: PhantomData,
+            }),
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.strategy.name()
+    }
+
+    pub fn interval_ms(&self) -> u64 {
+        self.strategy.interval_ms()
+    }
+
+    pub fn triggers(&self) -> Vec<Trigger> {
+        self.strategy.triggers()
+    }
+
+    pub fn debounce_ms(&self) -> u64 {
+        self.strategy.debounce_ms()
+    }
+
+    pub fn request_timeout_ms(&self) -> u64 {
+        self.strategy.request_timeout_ms()
+    }
+
+    pub fn input_policy(&self) -> InputPolicy {
+        self.strategy.input_policy()
+    }
+
+    /// Builds the strategy input from the state engine responses and evaluates it, `missing`
+    /// names the engines that did not respond
+    pub fn evaluate(&self, data: Vec<D>, missing: &[&str]) -> Result<Vec<A>> {
+        self.strategy.evaluate(data, missing)
+    }
+}
+
+impl<D, A> std::fmt::Debug for RegisteredStrategy<D, A> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RegisteredStrategy")
+            .field("id", &self.id)
+            .field("name", &self.strategy.name())
+            .finish()
+    }
+}
+
+trait ErasedStrategy<D, A>: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn interval_ms(&self) -> u64;
+
+    fn triggers(&self) -> Vec<Trigger>;
+
+    fn debounce_ms(&self) -> u64;
+
+    fn request_timeout_ms(&self) -> u64;
+
+    fn input_policy(&self) -> InputPolicy;
+
+    fn evaluate(&self, data: Vec<D>, missing: &[&str]) -> Result<Vec<A>>;
+}
+
+struct Typed<S, I> {
+    strategy: S,
+    input: PhantomData<fn() -> I>,
+}
+
+impl<S, D, I, A> ErasedStrategy<D, A> for Typed<S, I>
+where
+    S: Strategy<D, I, A>,
+{
+    fn name(&self) -> &'static str {
+        self.strategy.name()
+    }
+
+    fn interval_ms(&self) -> u64 {
+        self.strategy.interval_ms()
+    }
+
+    fn triggers(&self) -> Vec<Trigger> {
+        self.strategy.triggers()
+    }
+
+    fn debounce_ms(&self) -> u64 {
+        self.strategy.debounce_ms()
+    }
+
+    fn request_timeout_ms(&self) -> u64 {
+        self.strategy.request_timeout_ms()
+    }
+
+    fn input_policy(&self) -> InputPolicy {
+        self.strategy.input_policy()
+    }
+
+    fn evaluate(&self, data: Vec<D>, missing: &[&str]) -> Result<Vec<A>> {
+        let mut input_builder = S::InputBuilder::default();
+        for data in data {
+            input_builder.insert(data);
+        }
+        for engine in missing {
+            input_builder.missing(engine);
+        }
+
+        Ok(self.strategy.evaluate(input_builder.build()?))
+    }
+}
@@ -1,82 +1,127 @@
 use chrono::DateTime;
 use exstreamer::models::{BinanceTrade, BybitTradeData, CoinbaseTicker};
+use serde::{Deserialize, Serialize};
 
-use crate::models::event::EventSource;
+use crate::models::{
+    event::EventSource,
+    instrument::{Instrument, InstrumentKind, InstrumentRegistry},
+};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Trade {
     pub source: EventSource,
+    pub instrument: Instrument,
     pub price: f64,
     pub size: f64,
     pub timestamp: u64,
 }
 
-impl TryFrom<BinanceTrade> for Trade {
-    type Error = anyhow::Error;
+impl Trade {
+    /// Builds a trade from the string fields venues report, resolving the venue-native symbol
+    /// within the market category against the registry
+    pub fn from_raw(
+        source: EventSource,
+        kind: InstrumentKind,
+        symbol: &str,
+        price: &str,
+        size: &str,
+        timestamp: u64,
+        registry: &InstrumentRegistry,
+    ) -> anyhow::Result<Self> {
+        let instrument = registry
+            .resolve(&source, kind, symbol)
+            .cloned()
+            .ok_or_else(|| {
+                anyhow::anyhow!("Unknown {:?} {:?} symbol: '{}'", source, kind, symbol)
+            })?;
 
-    fn try_from(trade: BinanceTrade) -> Result<Self, Self::Error> {
-        match (trade.price.parse::<f64>(), trade.quantity.parse::<f64>()) {
-            (Ok(price), Ok(quantity)) => Ok(Trade {
-                source: EventSource::Binance,
+        match (price.parse::<f64>(), size.parse::<f64>()) {
+            (Ok(price), Ok(size)) => Ok(Trade {
+                source,
+                instrument,
                 price,
-                size: quantity,
-                timestamp: trade.trade_time,
+                size,
+                timestamp,
             }),
             _ => Err(anyhow::anyhow!(
-                "Failed to parse Binance trade from string to f64: price='{}', quantity='{}'",
-                trade.price,
-                trade.quantity
+                "Failed to parse {:?} trade from string to f64: price='{}', size='{}'",
+                source,
+                price,
+                size
             )),
         }
     }
 }
 
-impl TryFrom<BybitTradeData> for Trade {
+impl TryFrom<(BinanceTrade, InstrumentKind, &InstrumentRegistry)> for Trade {
     type Error = anyhow::Error;
 
-    fn try_from(data: BybitTradeData) -> Result<Self, Self::Error> {
-        match (data.price.parse::<f64>(), data.size.parse::<f64>()) {
-            (Ok(price), Ok(quantity)) => Ok(Trade {
-                source: EventSource::Bybit,
-                price,
-                size: quantity,
-                timestamp: data.timestamp,
-            }),
-            _ => Err(anyhow::anyhow!(
-                "Failed to parse trade from string to f64: price='{}', quantity='{}'",
-                data.price,
-                data.size
-            )),
-        }
+    fn try_from(
+        (trade, kind, registry): (BinanceTrade, InstrumentKind, &InstrumentRegistry),
+    ) -> Result<Self, Self::Error> {
+        Trade::from_raw(
+            EventSource::Binance,
+            kind,
+            &trade.symbol,
+            &trade.price,
+            &trade.quantity,
+            trade.trade_time,
+            registry,
+        )
     }
 }
 
-impl TryFrom<CoinbaseTicker> for Trade {
+impl TryFrom<(BybitTradeData, InstrumentKind, &InstrumentRegistry)> for Trade {
     type Error = anyhow::Error;
 
-    fn try_from(data: CoinbaseTicker) -> Result<Self, Self::Error> {
-        match (
-            data.price.parse::<f64>(),
-            data.last_size.parse::<f64>(),
-            parse_iso8601_to_timestamp(&data.time),
-        ) {
-            (Ok(price), Ok(size), Ok(timestamp)) => Ok(Trade {
-                source: EventSource::Coinbase,
-                price,
-                size,
-                timestamp,
-            }),
-            _ => Err(anyhow::anyhow!(
-                "Failed to parse Coinbase trade: price='{}', size='{}', time='{}'",
-                data.price,
-                data.last_size,
-                data.time
-            )),
-        }
+    fn try_from(
+        (data, kind, registry): (BybitTradeData, InstrumentKind, &InstrumentRegistry),
+    ) -> Result<Self, Self::Error> {
+        Trade::from_raw(
+            EventSource::Bybit,
+            kind,
+            &data.symbol,
+            &data.price,
+            &data.size,
+            data.timestamp,
+            registry,
+        )
+    }
+}
+
+impl TryFrom<(CoinbaseTicker, InstrumentKind, &InstrumentRegistry)> for Trade {
+    type Error = anyhow::Error;
+
+    fn try_from(
+        (data, kind, registry): (CoinbaseTicker, InstrumentKind, &InstrumentRegistry),
+    ) -> Result<Self, Self::Error> {
+        let timestamp = parse_iso8601_to_timestamp(&data.time).map_err(|e| {
+            anyhow::anyhow!("Failed to parse Coinbase trade time '{}': {}", data.time, e)
+        })?;
+
+        Trade::from_raw(
+            EventSource::Coinbase,
+            kind,
+            &data.product_id,
+            &data.price,
+            &data.last_size,
+            timestamp,
+            registry,
+        )
     }
 }
 
-fn parse_iso8601_to_timestamp(time_str: &str) -> Result<u64, chrono::ParseError> {
+/// Normalizes a venue-native symbol (`btcusdt`, `BTC-USD`) into an uppercase identifier
+/// without separators (`BTCUSDT`, `BTCUSD`)
+pub fn normalize_symbol(symbol: &str) -> String {
+    symbol
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+pub(crate) fn parse_iso8601_to_timestamp(time_str: &str) -> Result<u64, chrono::ParseError> {
     let datetime = DateTime::parse_from_rfc3339(time_str)?;
     Ok(datetime.timestamp_millis() as u64)
 }
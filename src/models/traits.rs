@@ -1,16 +1,44 @@
 use std::pin::Pin;
 
 use anyhow::Result;
-use tokio::sync::oneshot;
+use tokio::sync::{broadcast, oneshot};
 use tokio_stream::Stream;
 
+use crate::models::{
+    error::CollectorError, event::ConnectionStatus, lag::LagPolicy, queue::QueuePolicy,
+    reconnect::ReconnectPolicy, route::ActionFilter, strategy::InputPolicy, trigger::Trigger,
+};
+
 pub type CollectorStream<'a, E> = Pin<Box<dyn Stream<Item = E> + Send + 'a>>;
 
 #[async_trait::async_trait]
 pub trait Collector<E>: Send + Sync {
     fn name(&self) -> &'static str;
 
-    async fn get_event_stream(&self) -> Result<CollectorStream<'_, E>>;
+    async fn get_event_stream(&self) -> Result<CollectorStream<'_, E>, CollectorError>;
+
+    /// Whether the event stream ends once all of its events were sent, e.g. a replay of recorded
+    /// files, rather than when the connection is lost. Finite collectors are not reconnected
+    /// when their stream ends, and the bot drains and shuts down once every collector exited
+    fn is_finite(&self) -> bool {
+        false
+    }
+
+    /// Backoff applied by the bot when the event stream fails to connect or ends
+    fn reconnect_policy(&self) -> ReconnectPolicy {
+        ReconnectPolicy::default()
+    }
+
+    /// Event published on the bus when the collector loses or regains its stream
+    fn connection_event(&self, _status: ConnectionStatus) -> Option<E> {
+        None
+    }
+
+    /// Whether a state engine request asks the collector to resubscribe, the bot then
+    /// reconnects it right away so the venue sends fresh snapshots
+    fn resync_requested(&self, _request: &E) -> bool {
+        false
+    }
 }
 
 #[async_trait::async_trait]
@@ -24,6 +52,15 @@ pub trait StateEngine<E, D>: Send + Sync {
     fn process_request(&self, request: OneShot<D>) -> Result<()>;
 
     fn on_shutdown(&mut self) -> Result<()>;
+
+    /// Recovery applied by the bot when the engine falls behind the event bus
+    fn lag_policy(&self) -> LagPolicy {
+        LagPolicy::Skip
+    }
+
+    /// Called by the bot before starting the engine, lets it send requests such as resyncs to
+    /// the collectors
+    fn set_request_publisher(&mut self, _publisher: EventPublisher<E>) {}
 }
 
 pub trait Strategy<D, I, A>: Send + Sync {
@@ -31,21 +68,120 @@ pub trait Strategy<D, I, A>: Send + Sync {
 
     fn name(&self) -> &'static str;
 
+    /// Fixed evaluation interval, zero evaluates on triggers only
     fn interval_ms(&self) -> u64;
 
+    /// Market activity evaluating the strategy besides the interval, none by default
+    fn triggers(&self) -> Vec<Trigger> {
+        Vec::new()
+    }
+
+    /// Minimum time between evaluations caused by triggers, triggers firing sooner are
+    /// coalesced into a single evaluation once it has passed
+    fn debounce_ms(&self) -> u64 {
+        0
+    }
+
+    /// Deadline for each state engine to respond to a request for the strategy input
+    fn request_timeout_ms(&self) -> u64 {
+        1_000
+    }
+
+    /// Whether the strategy is evaluated when some state engines did not respond
+    fn input_policy(&self) -> InputPolicy {
+        InputPolicy::Complete
+    }
+
     fn evaluate(&self, input: I) -> Vec<A>;
 }
 
 #[async_trait::async_trait]
-pub trait Executor<A>: Send + Sync {
+pub trait Executor<A, E>: Send + Sync {
     fn name(&self) -> &'static str;
 
     async fn execute(&self, action: A) -> Result<()>;
+
+    /// Queue the bot keeps actions in until the executor takes them
+    fn queue_policy(&self) -> QueuePolicy {
+        QueuePolicy::default()
+    }
+
+    /// Key of the action, e.g. its instrument, actions with the same key execute in the order
+    /// they were emitted. Actions without a key may execute in any order when running
+    /// concurrently
+    fn action_key(&self, _action: &A) -> Option<String> {
+        None
+    }
+
+    /// Key under which a newer action replaces a queued one when the queue coalesces, e.g. the
+    /// order an amend targets. Actions without a key are never replaced
+    fn coalesce_key(&self, _action: &A) -> Option<String> {
+        None
+    }
+
+    /// Key of what the action acts on, e.g. the order a cancel targets. Actions dropped from a
+    /// full queue take the later actions with the same key with them, as those depend on it
+    fn dependency_key(&self, _action: &A) -> Option<String> {
+        None
+    }
+
+    /// Called by the bot when an action is routed to the executor, before it is queued, e.g. to
+    /// assign it an id and track it. Failing drops the action
+    fn register(&self, _action: &mut A) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called for a registered action dropped from the queue without being executed
+    fn discard(&self, _action: &A) {}
+
+    /// Actions the bot routes to the executor, unless its routing table says otherwise
+    fn action_filter(&self) -> ActionFilter {
+        ActionFilter::any()
+    }
+
+    /// Called by the bot before starting the executor, lets it publish events such as
+    /// execution reports onto the bus the collectors use
+    fn set_event_publisher(&mut self, _publisher: EventPublisher<E>) {}
+}
+
+/// Persists events seen on the bus, the bot feeds it every event and flushes it on shutdown
+pub trait Recorder<E>: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn record(&mut self, event: &E) -> Result<()>;
+
+    fn flush(&mut self) -> Result<()>;
+}
+
+/// Events carrying the time they occurred at, used to drive simulated time in backtests
+pub trait Timestamped {
+    /// Milliseconds since the Unix epoch, `None` for events without a market time
+    fn timestamp(&self) -> Option<u64>;
+}
+
+/// Actions carrying the id of the strategy that emitted them
+pub trait Attributed {
+    fn strategy_id(&self) -> Option<&str>;
+
+    /// Called by the bot on every action a strategy emits
+    fn set_strategy_id(&mut self, strategy_id: &str);
+}
+
+/// Plain text actions, as emitted by the echo strategy, carry no attribution
+impl Attributed for String {
+    fn strategy_id(&self) -> Option<&str> {
+        None
+    }
+
+    fn set_strategy_id(&mut self, _strategy_id: &str) {}
 }
 
 pub trait InputBuilder<D, I>: Send + Sync {
     fn insert(&mut self, data: D);
 
+    /// Called for every state engine that did not respond, before `build`
+    fn missing(&mut self, _engine: &str) {}
+
     fn build(self) -> Result<I>;
 }
 
@@ -68,3 +204,30 @@ impl<D> OneShot<D> {
         Ok(())
     }
 }
+
+#[derive(Debug)]
+pub struct EventPublisher<E> {
+    sender: broadcast::Sender<E>,
+}
+
+impl<E> Clone for EventPublisher<E> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+impl<E> EventPublisher<E> {
+    pub fn new(sender: broadcast::Sender<E>) -> Self {
+        Self { sender }
+    }
+
+    pub fn publish(&self, event: E) -> Result<()> {
+        self.sender
+            .send(event)
+            .map_err(|_| anyhow::anyhow!("Failed to publish event, no active receivers"))?;
+
+        Ok(())
+    }
+}
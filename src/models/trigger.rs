@@ -0,0 +1,218 @@
+use std::collections::HashMap;
+
+use crate::models::{
+    book::{BookKey, BookUpdate, OrderBook},
+    event::EventSource,
+    trade::Trade,
+};
+
+/// Market activity that makes the bot evaluate a strategy outside its interval
+#[derive(Debug, Clone, PartialEq)]
+pub enum Trigger {
+    /// Every `n` events seen on the bus since the last evaluation
+    EveryEvents(usize),
+    /// Every event of a type, e.g. `"Trade"`, optionally only from one source
+    EventType {
+        event_type: String,
+        source: Option<EventSource>,
+    },
+    /// Changes of the best bid or ask price, optionally only on one source
+    BookTop { source: Option<EventSource> },
+    /// Trade price moving at least `bps` basis points away from the price of the instrument at
+    /// the last evaluation
+    PriceMove { bps: f64 },
+}
+
+/// Events strategy triggers can be matched against
+pub trait TriggerEvent {
+    fn event_type(&self) -> String;
+
+    fn source(&self) -> Option<&EventSource>;
+
+    fn trade(&self) -> Option<&Trade> {
+        None
+    }
+
+    /// Book update carried by the event and whether it is a full snapshot
+    fn book_update(&self) -> Option<(&BookUpdate, bool)> {
+        None
+    }
+}
+
+/// Market state a set of triggers is evaluated against, kept between evaluations
+#[derive(Debug, Default)]
+pub struct TriggerState {
+    triggers: Vec<Trigger>,
+    events_since_evaluation: usize,
+    /// Books of the sources a top of book trigger watches
+    books: HashMap<BookKey, OrderBook>,
+    last_prices: HashMap<BookKey, f64>,
+    /// Trade prices at the last evaluation, price move triggers are measured from these
+    reference_prices: HashMap<BookKey, f64>,
+}
+
+impl TriggerState {
+    pub fn new(triggers: Vec<Trigger>) -> Self {
+        Self {
+            triggers,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.triggers.is_empty()
+    }
+
+    /// Updates the state with an event, returning whether a trigger fired
+    pub fn observe<E: TriggerEvent>(&mut self, event: &E) -> bool {
+        if self.triggers.is_empty() {
+            return false;
+        }
+
+        self.events_since_evaluation += 1;
+        let top_changed = self.apply_book(event);
+        let moved_bps = self.apply_trade(event);
+
+        self.triggers.iter().any(|trigger| match trigger {
+            Trigger::EveryEvents(n) => *n > 0 && self.events_since_evaluation >= *n,
+            Trigger::EventType { event_type, source } => {
+                event.event_type() == *event_type
+                    && source.as_ref().is_none_or(|s| event.source() == Some(s))
+            }
+            Trigger::BookTop { source } => {
+                top_changed && source.as_ref().is_none_or(|s| event.source() == Some(s))
+            }
+            Trigger::PriceMove { bps } => moved_bps.is_some_and(|moved| moved >= *bps),
+        })
+    }
+
+    /// Measures the following events from the current state, once the strategy was evaluated
+    pub fn evaluated(&mut self) {
+        self.events_since_evaluation = 0;
+        self.reference_prices.clone_from(&self.last_prices);
+    }
+
+    /// Applies a book update to the watched books, returning whether the top of book changed
+    fn apply_book<E: TriggerEvent>(&mut self, event: &E) -> bool {
+        let Some((update, snapshot)) = event.book_update() else {
+            return false;
+        };
+        if !self.watches_book(&update.source) {
+            return false;
+        }
+
+        let book = self
+            .books
+            .entry((update.source.clone(), update.instrument.clone()))
+            .or_default();
+        let top = |book: &OrderBook| {
+            (
+                book.best_bid().map(|level| level.price),
+                book.best_ask().map(|level| level.price),
+            )
+        };
+
+        let before = top(book);
+        if snapshot {
+            book.apply_snapshot(update);
+        } else if let Err(e) = book.apply_delta(update) {
+            // The book resyncs on the next snapshot, until then its top is unknown
+            tracing::debug!("Top of book trigger lost its book: {}", e);
+        }
+        top(book) != before
+    }
+
+    /// Records a trade price, returning how far it moved from the reference price in basis points
+    fn apply_trade<E: TriggerEvent>(&mut self, event: &E) -> Option<f64> {
+        let trade = event.trade()?;
+        let key = (trade.source.clone(), trade.instrument.clone());
+
+        self.last_prices.insert(key.clone(), trade.price);
+        let reference = *self.reference_prices.entry(key).or_insert(trade.price);
+        (reference > 0.0).then(|| (trade.price - reference).abs() / reference * 10_000.0)
+    }
+
+    fn watches_book(&self, source: &EventSource) -> bool {
+        self.triggers.iter().any(|trigger| match trigger {
+            Trigger::BookTop { source: watched } => watched.as_ref().is_none_or(|s| s == source),
+            _ => false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{
+            book::{Level, Sequence},
+            event::InternalEvent,
+        },
+        test_support as support,
+    };
+
+    fn trade(source: EventSource, price: f64) -> InternalEvent {
+        InternalEvent::Trade(support::trade(source, price, 1.0, 0))
+    }
+
+    fn snapshot(source: EventSource, bid: f64, ask: f64) -> InternalEvent {
+        InternalEvent::BookSnapshot(BookUpdate {
+            source,
+            instrument: support::instrument(),
+            bids: vec![Level {
+                price: bid,
+                size: 1.0,
+            }],
+            asks: vec![Level {
+                price: ask,
+                size: 1.0,
+            }],
+            sequence: Some(Sequence { first: 1, last: 1 }),
+            timestamp: 0,
+        })
+    }
+
+    #[test]
+    fn event_type_triggers_match_the_source() {
+        let mut state = TriggerState::new(vec![Trigger::EventType {
+            event_type: "Trade".to_string(),
+            source: Some(EventSource::Bybit),
+        }]);
+
+        assert!(!state.observe(&trade(EventSource::Binance, 100.0)));
+        assert!(state.observe(&trade(EventSource::Bybit, 100.0)));
+        assert!(!state.observe(&snapshot(EventSource::Bybit, 99.0, 101.0)));
+    }
+
+    #[test]
+    fn every_events_counts_from_the_last_evaluation() {
+        let mut state = TriggerState::new(vec![Trigger::EveryEvents(2)]);
+
+        assert!(!state.observe(&trade(EventSource::Binance, 100.0)));
+        assert!(state.observe(&trade(EventSource::Binance, 100.0)));
+        state.evaluated();
+        assert!(!state.observe(&trade(EventSource::Binance, 100.0)));
+    }
+
+    #[test]
+    fn book_top_triggers_on_changes_of_the_best_prices() {
+        let mut state = TriggerState::new(vec![Trigger::BookTop { source: None }]);
+
+        assert!(state.observe(&snapshot(EventSource::Binance, 99.0, 101.0)));
+        assert!(!state.observe(&snapshot(EventSource::Binance, 99.0, 101.0)));
+        assert!(state.observe(&snapshot(EventSource::Binance, 99.5, 101.0)));
+    }
+
+    #[test]
+    fn price_moves_are_measured_from_the_last_evaluation() {
+        let mut state = TriggerState::new(vec![Trigger::PriceMove { bps: 10.0 }]);
+
+        assert!(!state.observe(&trade(EventSource::Binance, 100.0)));
+        assert!(!state.observe(&trade(EventSource::Binance, 100.05)));
+        assert!(state.observe(&trade(EventSource::Binance, 100.2)));
+
+        state.evaluated();
+        assert!(!state.observe(&trade(EventSource::Binance, 100.25)));
+        assert!(state.observe(&trade(EventSource::Binance, 100.0)));
+    }
+}
@@ -0,0 +1,524 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use serde::Serialize;
+use tokio_stream::StreamExt as _;
+
+use crate::{
+    backtest::{BacktestSummary, run_backtest},
+    models::{
+        ActionFilter, Collector, CollectorError, CollectorStream, Executor, InternalEvent,
+        ReconnectPolicy, Routable, SimulatedClock, StateEngine, StateOutput, Strategy, Timestamped,
+    },
+    report::{PerformanceReport, PerformanceTracker},
+};
+
+/// Named parameter values of a single backtest
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ParamSet(BTreeMap<String, f64>);
+
+impl ParamSet {
+    pub fn get(&self, name: &str) -> anyhow::Result<f64> {
+        self.0
+            .get(name)
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("Missing parameter '{}'", name))
+    }
+
+    pub fn with(mut self, name: &str, value: f64) -> Self {
+        self.0.insert(name.to_string(), value);
+        self
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &f64)> {
+        self.0.iter()
+    }
+}
+
+impl std::fmt::Display for ParamSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let params = self
+            .0
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>();
+        write!(f, "{}", params.join(", "))
+    }
+}
+
+/// Every combination of a list of candidate values per parameter
+#[derive(Debug, Clone, Default)]
+pub struct ParameterGrid {
+    params: Vec<(String, Vec<f64>)>,
+}
+
+impl ParameterGrid {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, name: &str, values: impl IntoIterator<Item = f64>) -> Self {
+        self.params
+            .push((name.to_string(), values.into_iter().collect()));
+        self
+    }
+
+    pub fn combinations(&self) -> Vec<ParamSet> {
+        self.params
+            .iter()
+            .fold(vec![ParamSet::default()], |sets, (name, values)| {
+                sets.iter()
+                    .flat_map(|set| values.iter().map(|&value| set.clone().with(name, value)))
+                    .collect()
+            })
+    }
+}
+
+/// Uniformly sampled values within a range per parameter
+#[derive(Debug, Clone, Default)]
+pub struct RandomSearch {
+    ranges: Vec<(String, f64, f64)>,
+}
+
+impl RandomSearch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Samples the parameter within `[min, max]`
+    pub fn with(mut self, name: &str, min: f64, max: f64) -> Self {
+        self.ranges.push((name.to_string(), min, max));
+        self
+    }
+
+    /// Draws `samples` parameter sets, the same seed always drawing the same sets
+    pub fn sample(&self, samples: usize, seed: u64) -> Vec<ParamSet> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..samples)
+            .map(|_| {
+                self.ranges
+                    .iter()
+                    .fold(ParamSet::default(), |set, (name, min, max)| {
+                        set.with(name, rng.random_range(*min..=*max))
+                    })
+            })
+            .collect()
+    }
+}
+
+/// Score a backtest is ranked by, higher is better
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Objective {
+    NetPnl,
+    Sharpe,
+    Sortino,
+    /// Net PnL divided by the maximum drawdown
+    Calmar,
+    /// Smallest maximum drawdown, as a fraction of the peak
+    MinDrawdown,
+}
+
+impl Objective {
+    /// Scores a report, runs without enough data for a ratio score negative infinity
+    pub fn score(&self, report: &PerformanceReport) -> f64 {
+        let score = match self {
+            Objective::NetPnl => Some(report.total.net_pnl()),
+            Objective::Sharpe => report.sharpe,
+            Objective::Sortino => report.sortino,
+            Objective::Calmar => {
+                (report.max_drawdown > 0.0).then(|| report.total.net_pnl() / report.max_drawdown)
+            }
+            Objective::MinDrawdown => Some(-report.max_drawdown_pct),
+        };
+        score
+            .filter(|score| score.is_finite())
+            .unwrap_or(f64::NEG_INFINITY)
+    }
+}
+
+/// Range of event timestamps replayed, in milliseconds since the Unix epoch, end exclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct TimeWindow {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl TimeWindow {
+    pub fn contains(&self, timestamp: u64) -> bool {
+        (self.start..self.end).contains(&timestamp)
+    }
+}
+
+/// Components of one backtest, built fresh for every parameter set
+pub struct BacktestSetup<S, A> {
+    /// Id the strategy's actions are attributed to
+    pub strategy_id: String,
+    pub strategy: S,
+    pub states: Vec<Box<dyn StateEngine<InternalEvent, StateOutput>>>,
+    pub collectors: Vec<Box<dyn Collector<InternalEvent>>>,
+    pub executors: Vec<Box<dyn Executor<A, InternalEvent>>>,
+    /// Actions routed to each executor by name, replacing the filter the executor declares
+    pub routes: HashMap<String, ActionFilter>,
+    /// Tracker the run is scored from, its engine is registered by the runner
+    pub tracker: PerformanceTracker,
+    /// Clock shared with the components, driven by the backtest
+    pub clock: Arc<SimulatedClock>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SweepResult {
+    pub params: ParamSet,
+    pub score: f64,
+    pub report: PerformanceReport,
+    pub summary: BacktestSummary,
+}
+
+/// Runs a backtest for every parameter set in parallel, best score first
+///
+/// `build` creates the components of each run from its parameters. Each run gets its own
+/// single-threaded runtime on the rayon pool, so this must not be called from async code
+/// without `spawn_blocking`. Events outside `window` are dropped when one is given. Runs
+/// that fail are logged and left out of the results.
+pub fn sweep<S, I, A, F>(
+    candidates: Vec<ParamSet>,
+    build: F,
+    objective: Objective,
+    window: Option<TimeWindow>,
+) -> Vec<SweepResult>
+where
+    S: Strategy<StateOutput, I, A> + 'static,
+    I: 'static,
+    A: Routable + Clone + Send + Sync + 'static,
+    F: Fn(&ParamSet) -> anyhow::Result<BacktestSetup<S, A>> + Send + Sync,
+{
+    let mut results = candidates
+        .into_par_iter()
+        .filter_map(|params| match run_one(&params, &build, objective, window) {
+            Ok(result) => Some(result),
+            Err(e) => {
+                tracing::warn!("Backtest with {} failed: {}", params, e);
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+
+    results.sort_by(|a, b| b.score.total_cmp(&a.score));
+    results
+}
+
+fn run_one<S, I, A, F>(
+    params: &ParamSet,
+    build: &F,
+    objective: Objective,
+    window: Option<TimeWindow>,
+) -> anyhow::Result<SweepResult>
+where
+    S: Strategy<StateOutput, I, A> + 'static,
+    I: 'static,
+    A: Routable + Clone + Send + Sync + 'static,
+    F: Fn(&ParamSet) -> anyhow::Result<BacktestSetup<S, A>>,
+{
+    let BacktestSetup {
+        strategy_id,
+        strategy,
+        mut states,
+        collectors,
+        executors,
+        routes,
+        tracker,
+        clock,
+    } = build(params)?;
+    states.push(Box::new(tracker.engine()));
+
+    let collectors = match window {
+        Some(window) => collectors
+            .into_iter()
+            .map(|inner| {
+                Box::new(WindowedCollector { inner, window }) as Box<dyn Collector<InternalEvent>>
+            })
+            .collect(),
+        None => collectors,
+    };
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    let summary = runtime.block_on(run_backtest(
+        &strategy_id,
+        strategy,
+        states,
+        collectors,
+        executors,
+        &routes,
+        clock,
+    ))?;
+
+    let report = tracker.report();
+    Ok(SweepResult {
+        params: params.clone(),
+        score: objective.score(&report),
+        report,
+        summary,
+    })
+}
+
+/// Consecutive train and test windows rolled forward through the data
+#[derive(Debug, Clone, Copy)]
+pub struct WalkForward {
+    /// Span of the data split into folds
+    pub range: TimeWindow,
+    pub train_ms: u64,
+    pub test_ms: u64,
+    /// How far each fold moves forward, usually `test_ms` so test windows do not overlap
+    pub step_ms: u64,
+}
+
+impl WalkForward {
+    pub fn new(range: TimeWindow, train_ms: u64, test_ms: u64) -> Self {
+        Self {
+            range,
+            train_ms,
+            test_ms,
+            step_ms: test_ms,
+        }
+    }
+
+    /// Train and test window of every fold that fits within the range
+    pub fn folds(&self) -> Vec<(TimeWindow, TimeWindow)> {
+        let mut folds = Vec::new();
+        let mut start = self.range.start;
+
+        while start + self.train_ms + self.test_ms <= self.range.end {
+            let train = TimeWindow {
+                start,
+                end: start + self.train_ms,
+            };
+            let test = TimeWindow {
+                start: train.end,
+                end: train.end + self.test_ms,
+            };
+            folds.push((train, test));
+            start += self.step_ms.max(1);
+        }
+
+        folds
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FoldResult {
+    pub train: TimeWindow,
+    pub test: TimeWindow,
+    /// Best parameters on the train window and their results on it
+    pub best: SweepResult,
+    /// Results of the best parameters on the unseen test window
+    pub out_of_sample: SweepResult,
+}
+
+/// Optimizes on each train window and evaluates the winner on the following test window
+///
+/// The out-of-sample results of all folds together estimate how the parameter selection
+/// process performs on data it has not seen.
+pub fn walk_forward<S, I, A, F>(
+    candidates: Vec<ParamSet>,
+    build: F,
+    objective: Objective,
+    folds: &WalkForward,
+) -> Vec<FoldResult>
+where
+    S: Strategy<StateOutput, I, A> + 'static,
+    I: 'static,
+    A: Routable + Clone + Send + Sync + 'static,
+    F: Fn(&ParamSet) -> anyhow::Result<BacktestSetup<S, A>> + Send + Sync,
+{
+    let mut results = Vec::new();
+
+    for (train, test) in folds.folds() {
+        let Some(best) = sweep(candidates.clone(), &build, objective, Some(train))
+            .into_iter()
+            .next()
+        else {
+            tracing::warn!("No successful backtest for train window {:?}", train);
+            continue;
+        };
+
+        match run_one(&best.params, &build, objective, Some(test)) {
+            Ok(out_of_sample) => {
+                tracing::info!(
+                    "Fold {:?}: best {} scored {} in sample, {} out of sample",
+                    test,
+                    best.params,
+                    best.score,
+                    out_of_sample.score
+                );
+                results.push(FoldResult {
+                    train,
+                    test,
+                    best,
+                    out_of_sample,
+                });
+            }
+            Err(e) => tracing::warn!("Backtest for test window {:?} failed: {}", test, e),
+        }
+    }
+
+    results
+}
+
+/// Restricts a collector to the events within a time window
+///
+/// The inner stream is read to its end, as event timestamps need not be in order, e.g. the
+/// exchange timestamps of a recording ordered by receive time.
+struct WindowedCollector {
+    inner: Box<dyn Collector<InternalEvent>>,
+    window: TimeWindow,
+}
+
+#[async_trait::async_trait]
+impl Collector<InternalEvent> for WindowedCollector {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    async fn get_event_stream(&self) -> Result<CollectorStream<'_, InternalEvent>, CollectorError> {
+        let window = self.window;
+        let stream = self
+            .inner
+            .get_event_stream()
+            .await?
+            .filter(move |event| event.timestamp().is_none_or(|ts| window.contains(ts)));
+
+        Ok(Box::pin(stream))
+    }
+
+    fn reconnect_policy(&self) -> ReconnectPolicy {
+        self.inner.reconnect_policy()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::PnlBreakdown;
+
+    fn window(start: u64, end: u64) -> TimeWindow {
+        TimeWindow { start, end }
+    }
+
+    fn report(net_pnl: f64, max_drawdown: f64, sharpe: Option<f64>) -> PerformanceReport {
+        PerformanceReport {
+            start: Some(0),
+            end: Some(1_000),
+            initial_equity: 1_000.0,
+            final_equity: 1_000.0 + net_pnl,
+            total: PnlBreakdown {
+                realized_pnl: net_pnl,
+                ..PnlBreakdown::default()
+            },
+            max_drawdown,
+            max_drawdown_pct: max_drawdown / 1_000.0,
+            sharpe,
+            sortino: None,
+            instruments: Vec::new(),
+            venues: Vec::new(),
+            equity_curve: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn folds_roll_forward_within_the_range() {
+        let folds = WalkForward::new(window(0, 100), 40, 20).folds();
+
+        assert_eq!(
+            folds,
+            vec![
+                (window(0, 40), window(40, 60)),
+                (window(20, 60), window(60, 80)),
+                (window(40, 80), window(80, 100)),
+            ]
+        );
+        assert!(WalkForward::new(window(0, 50), 40, 20).folds().is_empty());
+    }
+
+    #[test]
+    fn folds_step_independently_of_the_test_window() {
+        let mut walk = WalkForward::new(window(0, 100), 40, 20);
+        walk.step_ms = 30;
+
+        let starts = walk
+            .folds()
+            .iter()
+            .map(|(train, _)| train.start)
+            .collect::<Vec<_>>();
+
+        assert_eq!(starts, vec![0, 30]);
+    }
+
+    #[test]
+    fn grid_yields_every_combination() {
+        let grid = ParameterGrid::new()
+            .with("fast", [1.0, 2.0])
+            .with("slow", [10.0, 20.0, 30.0]);
+
+        let combinations = grid.combinations();
+
+        assert_eq!(combinations.len(), 6);
+        assert_eq!(
+            combinations[0],
+            ParamSet::default().with("fast", 1.0).with("slow", 10.0)
+        );
+        assert_eq!(
+            combinations[5],
+            ParamSet::default().with("fast", 2.0).with("slow", 30.0)
+        );
+        assert_eq!(
+            ParameterGrid::new().combinations(),
+            vec![ParamSet::default()]
+        );
+        assert!(
+            ParameterGrid::new()
+                .with("fast", [])
+                .combinations()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn random_search_is_deterministic_per_seed() {
+        let search = RandomSearch::new()
+            .with("threshold", 0.5, 1.5)
+            .with("size", 10.0, 20.0);
+
+        let samples = search.sample(20, 7);
+
+        assert_eq!(samples, search.sample(20, 7));
+        assert_ne!(samples, search.sample(20, 8));
+        for set in &samples {
+            assert!((0.5..=1.5).contains(&set.get("threshold").unwrap()));
+            assert!((10.0..=20.0).contains(&set.get("size").unwrap()));
+        }
+    }
+
+    #[test]
+    fn objectives_score_higher_as_better() {
+        let report = report(50.0, 25.0, Some(1.5));
+
+        assert_eq!(Objective::NetPnl.score(&report), 50.0);
+        assert_eq!(Objective::Sharpe.score(&report), 1.5);
+        assert_eq!(Objective::Calmar.score(&report), 2.0);
+        assert_eq!(Objective::MinDrawdown.score(&report), -0.025);
+    }
+
+    #[test]
+    fn missing_ratios_score_negative_infinity() {
+        let report = report(50.0, 0.0, Some(f64::NAN));
+
+        assert_eq!(Objective::Sharpe.score(&report), f64::NEG_INFINITY);
+        assert_eq!(Objective::Sortino.score(&report), f64::NEG_INFINITY);
+        assert_eq!(Objective::Calmar.score(&report), f64::NEG_INFINITY);
+    }
+}
@@ -0,0 +1,521 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::Notify;
+
+use crate::{
+    metrics::BotMetrics,
+    models::{Executor, OverflowPolicy, QueuePolicy},
+};
+
+/// Action waiting in an executor queue
+#[derive(Debug)]
+pub(crate) struct Queued<A> {
+    pub key: Option<String>,
+    pub coalesce_key: Option<String>,
+    pub dependency_key: Option<String>,
+    pub action: A,
+}
+
+/// Creates the queue of an executor, strategies push onto the sender side and the executor task
+/// takes actions from the receiver side
+pub(crate) fn action_queue<A, E>(
+    executor: Arc<dyn Executor<A, E>>,
+) -> (ActionSender<A, E>, ActionReceiver<A>)
+where
+    A: 'static,
+    E: 'static,
+{
+    let discard_executor = executor.clone();
+    let shared = Arc::new(Shared {
+        name: executor.name(),
+        policy: executor.queue_policy(),
+        discard: Box::new(move |action| discard_executor.discard(action)),
+        state: Mutex::new(QueueState {
+            items: VecDeque::new(),
+            senders: 1,
+            closed: false,
+        }),
+        pushed: Notify::new(),
+        popped: Notify::new(),
+    });
+
+    let sender = ActionSender {
+        executor,
+        shared: shared.clone(),
+    };
+    (sender, ActionReceiver { shared })
+}
+
+struct Shared<A> {
+    name: &'static str,
+    policy: QueuePolicy,
+    /// Tells the executor about actions dropped without being executed
+    discard: Box<dyn Fn(&A) + Send + Sync>,
+    state: Mutex<QueueState<A>>,
+    /// Wakes the receiver once an action was pushed or the last sender dropped
+    pushed: Notify,
+    /// Wakes blocked senders once a slot was freed or the receiver dropped
+    popped: Notify,
+}
+
+struct QueueState<A> {
+    items: VecDeque<Queued<A>>,
+    senders: usize,
+    /// Whether the executor stopped taking actions
+    closed: bool,
+}
+
+/// Strategy side of an executor queue
+pub(crate) struct ActionSender<A, E> {
+    executor: Arc<dyn Executor<A, E>>,
+    shared: Arc<Shared<A>>,
+}
+
+impl<A, E> ActionSender<A, E> {
+    pub fn name(&self) -> &'static str {
+        self.shared.name
+    }
+
+    /// Registers a routed action with the executor, before it is queued
+    pub fn register(&self, action: &mut A) -> anyhow::Result<()> {
+        self.executor.register(action)
+    }
+
+    /// Tells the executor a registered action was dropped without being queued
+    pub fn discard(&self, action: &A) {
+        self.executor.discard(action);
+    }
+
+    /// Queues an action under the overflow policy, failing if the executor stopped for good
+    pub async fn push(&self, action: A) -> Result<(), A> {
+        let mut queued = self.queued(action);
+
+        loop {
+            let popped = self.shared.popped.notified();
+            tokio::pin!(popped);
+            popped.as_mut().enable();
+
+            match self.shared.try_push(queued) {
+                Push::Queued => {
+                    self.shared.pushed.notify_one();
+                    return Ok(());
+                }
+                Push::Dropped => return Ok(()),
+                Push::Closed(rejected) => return Err(rejected.action),
+                Push::Full(rejected) => {
+                    queued = rejected;
+                    popped.await;
+                }
+            }
+        }
+    }
+
+    /// Queues an action under the overflow policy without waiting, handing it back if the queue
+    /// is full or the executor stopped
+    pub fn try_push(&self, action: A) -> Result<(), A> {
+        match self.shared.try_push(self.queued(action)) {
+            Push::Queued => {
+                self.shared.pushed.notify_one();
+                Ok(())
+            }
+            Push::Dropped => Ok(()),
+            Push::Closed(rejected) | Push::Full(rejected) => Err(rejected.action),
+        }
+    }
+
+    fn queued(&self, action: A) -> Queued<A> {
+        Queued {
+            key: self.executor.action_key(&action),
+            coalesce_key: self.executor.coalesce_key(&action),
+            dependency_key: self.executor.dependency_key(&action),
+            action,
+        }
+    }
+}
+
+enum Push<A> {
+    Queued,
+    /// Dropped or coalesced under the overflow policy
+    Dropped,
+    Closed(Queued<A>),
+    /// The queue is full and the sender has to wait
+    Full(Queued<A>),
+}
+
+impl<A> Shared<A> {
+    fn lock(&self) -> std::sync::MutexGuard<'_, QueueState<A>> {
+        self.state.lock().expect("Action queue lock poisoned")
+    }
+
+    fn try_push(&self, queued: Queued<A>) -> Push<A> {
+        let mut discarded = Vec::new();
+        let push = self.push_locked(queued, &mut discarded);
+        for queued in discarded {
+            (self.discard)(&queued.action);
+        }
+        push
+    }
+
+    /// Queues an action, collecting the actions the overflow policy dropped
+    fn push_locked(&self, queued: Queued<A>, discarded: &mut Vec<Queued<A>>) -> Push<A> {
+        let mut state = self.lock();
+        if state.closed {
+            return Push::Closed(queued);
+        }
+
+        if self.policy.overflow == OverflowPolicy::Coalesce
+            && let Some(existing) = queued.coalesce_key.as_ref().and_then(|key| {
+                state
+                    .items
+                    .iter_mut()
+                    .find(|existing| existing.coalesce_key.as_ref() == Some(key))
+            })
+        {
+            discarded.push(std::mem::replace(existing, queued));
+            BotMetrics::record_action_dropped(self.name, "coalesced");
+            return Push::Dropped;
+        }
+
+        if state.items.len() >= self.policy.capacity.max(1) {
+            match self.policy.overflow {
+                OverflowPolicy::DropOldest | OverflowPolicy::Coalesce => {
+                    let oldest = state.items.pop_front().expect("Full action queue is empty");
+                    tracing::warn!(
+                        "Action queue of executor {} is full, dropped the oldest action",
+                        self.name
+                    );
+                    BotMetrics::record_action_dropped(self.name, "oldest");
+                    let key = oldest.dependency_key.clone();
+                    discarded.push(oldest);
+
+                    // Actions on what the oldest one acted on, e.g. the cancel of a dropped
+                    // order, cannot execute without it
+                    if let Some(key) = key {
+                        let depends =
+                            |queued: &Queued<A>| queued.dependency_key.as_ref() == Some(&key);
+                        let (dependent, kept) = std::mem::take(&mut state.items)
+                            .into_iter()
+                            .partition::<VecDeque<_>, _>(depends);
+                        state.items = kept;
+                        for queued in dependent {
+                            BotMetrics::record_action_dropped(self.name, "dependent");
+                            discarded.push(queued);
+                        }
+                        if depends(&queued) {
+                            BotMetrics::record_action_dropped(self.name, "dependent");
+                            discarded.push(queued);
+                            return Push::Dropped;
+                        }
+                    }
+                }
+                OverflowPolicy::DropNewest => {
+                    tracing::warn!(
+                        "Action queue of executor {} is full, dropped the new action",
+                        self.name
+                    );
+                    BotMetrics::record_action_dropped(self.name, "newest");
+                    discarded.push(queued);
+                    return Push::Dropped;
+                }
+                OverflowPolicy::Block => return Push::Full(queued),
+            }
+        }
+
+        state.items.push_back(queued);
+        BotMetrics::record_channel_queue("actions", self.name, state.items.len());
+        Push::Queued
+    }
+}
+
+impl<A, E> Clone for ActionSender<A, E> {
+    fn clone(&self) -> Self {
+        self.shared.lock().senders += 1;
+        Self {
+            executor: self.executor.clone(),
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<A, E> Drop for ActionSender<A, E> {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.senders -= 1;
+        if state.senders == 0 {
+            drop(state);
+            self.shared.pushed.notify_one();
+        }
+    }
+}
+
+/// Executor side of an executor queue, closes the queue when dropped
+pub(crate) struct ActionReceiver<A> {
+    shared: Arc<Shared<A>>,
+}
+
+impl<A> ActionReceiver<A> {
+    pub fn max_in_flight(&self) -> usize {
+        self.shared.policy.max_in_flight.max(1)
+    }
+
+    /// Takes the oldest action whose key is not in flight
+    pub fn pop_ready(&self, in_flight: &HashSet<String>) -> Option<Queued<A>> {
+        let mut state = self.shared.lock();
+        let index = state.items.iter().position(|queued| {
+            queued
+                .key
+                .as_ref()
+                .is_none_or(|key| !in_flight.contains(key))
+        })?;
+
+        let queued = state.items.remove(index);
+        BotMetrics::record_channel_queue("actions", self.shared.name, state.items.len());
+        drop(state);
+
+        self.shared.popped.notify_one();
+        queued
+    }
+
+    /// Whether every sender is gone and no action is left
+    pub fn is_finished(&self) -> bool {
+        let state = self.shared.lock();
+        state.senders == 0 && state.items.is_empty()
+    }
+
+    /// Waits until an action is pushed or the last sender is dropped
+    pub async fn pushed(&self) {
+        self.shared.pushed.notified().await;
+    }
+}
+
+impl<A> Drop for ActionReceiver<A> {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.closed = true;
+        let discarded = std::mem::take(&mut state.items);
+        drop(state);
+
+        for queued in discarded {
+            (self.shared.discard)(&queued.action);
+        }
+
+        self.shared.popped.notify_waiters();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    /// Action numbered in push order
+    #[derive(Debug, Clone, Default)]
+    struct Act {
+        id: u32,
+        key: Option<&'static str>,
+        coalesce_key: Option<&'static str>,
+        order: Option<&'static str>,
+    }
+
+    fn act(id: u32) -> Act {
+        Act {
+            id,
+            ..Default::default()
+        }
+    }
+
+    struct Recording {
+        policy: QueuePolicy,
+        discarded: Mutex<Vec<u32>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Executor<Act, ()> for Recording {
+        fn name(&self) -> &'static str {
+            "recording"
+        }
+
+        async fn execute(&self, _action: Act) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        fn queue_policy(&self) -> QueuePolicy {
+            self.policy.clone()
+        }
+
+        fn action_key(&self, action: &Act) -> Option<String> {
+            action.key.map(String::from)
+        }
+
+        fn coalesce_key(&self, action: &Act) -> Option<String> {
+            action.coalesce_key.map(String::from)
+        }
+
+        fn dependency_key(&self, action: &Act) -> Option<String> {
+            action.order.map(String::from)
+        }
+
+        fn discard(&self, action: &Act) {
+            self.discarded.lock().unwrap().push(action.id);
+        }
+    }
+
+    fn queue(
+        capacity: usize,
+        overflow: OverflowPolicy,
+    ) -> (Arc<Recording>, ActionSender<Act, ()>, ActionReceiver<Act>) {
+        let executor = Arc::new(Recording {
+            policy: QueuePolicy {
+                capacity,
+                overflow,
+                max_in_flight: 1,
+            },
+            discarded: Mutex::new(Vec::new()),
+        });
+        let (sender, receiver) = action_queue(executor.clone() as Arc<dyn Executor<Act, ()>>);
+        (executor, sender, receiver)
+    }
+
+    fn drain(receiver: &ActionReceiver<Act>) -> Vec<u32> {
+        std::iter::from_fn(|| receiver.pop_ready(&HashSet::new()))
+            .map(|queued| queued.action.id)
+            .collect()
+    }
+
+    fn discarded(executor: &Recording) -> Vec<u32> {
+        executor.discarded.lock().unwrap().clone()
+    }
+
+    #[test]
+    fn drop_oldest_makes_room_for_new_actions() {
+        let (executor, sender, receiver) = queue(2, OverflowPolicy::DropOldest);
+
+        for id in 1..=3 {
+            sender.try_push(act(id)).unwrap();
+        }
+
+        assert_eq!(drain(&receiver), vec![2, 3]);
+        assert_eq!(discarded(&executor), vec![1]);
+    }
+
+    #[test]
+    fn drop_oldest_takes_the_actions_depending_on_it() {
+        let (executor, sender, receiver) = queue(3, OverflowPolicy::DropOldest);
+        let on = |id, order| Act {
+            order: Some(order),
+            ..act(id)
+        };
+
+        // The order, another order, and an amend of the first one
+        sender.try_push(on(1, "a")).unwrap();
+        sender.try_push(on(2, "b")).unwrap();
+        sender.try_push(on(3, "a")).unwrap();
+        // A fourth action evicts the first order and its amend
+        sender.try_push(on(4, "c")).unwrap();
+        sender.try_push(act(5)).unwrap();
+        // Evicting the second order also drops its pushed cancel
+        sender.try_push(act(6)).unwrap();
+        sender.try_push(on(7, "c")).unwrap();
+
+        assert_eq!(discarded(&executor), vec![1, 3, 2, 4, 7]);
+        assert_eq!(drain(&receiver), vec![5, 6]);
+    }
+
+    #[test]
+    fn drop_newest_keeps_queued_actions() {
+        let (executor, sender, receiver) = queue(1, OverflowPolicy::DropNewest);
+
+        sender.try_push(act(1)).unwrap();
+        sender.try_push(act(2)).unwrap();
+
+        assert_eq!(drain(&receiver), vec![1]);
+        assert_eq!(discarded(&executor), vec![2]);
+    }
+
+    #[tokio::test]
+    async fn block_waits_for_a_free_slot() {
+        let (executor, sender, receiver) = queue(1, OverflowPolicy::Block);
+        sender.try_push(act(1)).unwrap();
+
+        assert_eq!(sender.try_push(act(2)).unwrap_err().id, 2);
+        let push = sender.push(act(3));
+        tokio::pin!(push);
+        assert!(
+            tokio::time::timeout(Duration::from_millis(20), push.as_mut())
+                .await
+                .is_err()
+        );
+
+        assert_eq!(drain(&receiver), vec![1]);
+        push.await.unwrap();
+        assert_eq!(drain(&receiver), vec![3]);
+        assert!(discarded(&executor).is_empty());
+    }
+
+    #[test]
+    fn coalesce_replaces_queued_actions_in_place() {
+        let (executor, sender, receiver) = queue(2, OverflowPolicy::Coalesce);
+        let keyed = |id, key| Act {
+            coalesce_key: Some(key),
+            ..act(id)
+        };
+
+        sender.try_push(keyed(1, "x")).unwrap();
+        sender.try_push(keyed(2, "y")).unwrap();
+        sender.try_push(keyed(3, "x")).unwrap();
+        // Without a queued action to replace, a full queue drops its oldest one
+        sender.try_push(keyed(4, "z")).unwrap();
+
+        assert_eq!(drain(&receiver), vec![2, 4]);
+        assert_eq!(discarded(&executor), vec![1, 3]);
+    }
+
+    #[test]
+    fn pop_ready_skips_keys_in_flight_and_keeps_their_order() {
+        let (_, sender, receiver) = queue(8, OverflowPolicy::Block);
+        let keyed = |id, key| Act {
+            key: Some(key),
+            ..act(id)
+        };
+        for action in [keyed(1, "a"), keyed(2, "a"), keyed(3, "b"), act(4)] {
+            sender.try_push(action).unwrap();
+        }
+
+        let in_flight = HashSet::from(["a".to_string()]);
+        assert_eq!(receiver.pop_ready(&in_flight).unwrap().action.id, 3);
+        assert_eq!(receiver.pop_ready(&in_flight).unwrap().action.id, 4);
+        assert!(receiver.pop_ready(&in_flight).is_none());
+        assert_eq!(drain(&receiver), vec![1, 2]);
+    }
+
+    #[test]
+    fn max_in_flight_is_at_least_one() {
+        let executor = |max_in_flight| {
+            Arc::new(Recording {
+                policy: QueuePolicy {
+                    max_in_flight,
+                    ..Default::default()
+                },
+                discarded: Mutex::new(Vec::new()),
+            }) as Arc<dyn Executor<Act, ()>>
+        };
+
+        assert_eq!(action_queue(executor(0)).1.max_in_flight(), 1);
+        assert_eq!(action_queue(executor(4)).1.max_in_flight(), 4);
+    }
+
+    #[test]
+    fn dropping_the_receiver_discards_queued_actions() {
+        let (executor, sender, receiver) = queue(4, OverflowPolicy::Block);
+        sender.try_push(act(1)).unwrap();
+
+        drop(receiver);
+
+        assert_eq!(discarded(&executor), vec![1]);
+        assert_eq!(sender.try_push(act(2)).unwrap_err().id, 2);
+    }
+}
@@ -0,0 +1,204 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use flate2::{Compression, write::GzEncoder};
+use serde::Serialize;
+
+use crate::models::{RecordedEvent, Recorder};
+
+#[derive(Debug, Clone)]
+pub struct FileRecorderConfig {
+    /// Directory recordings are written to, created if missing
+    pub dir: PathBuf,
+    /// File name prefix, files are named `{prefix}-{opened at}-{index}.jsonl[.gz]`
+    pub prefix: String,
+    /// Rotate once a file holds this many bytes of uncompressed records
+    pub max_file_bytes: u64,
+    /// Rotate once a file has been open this long, `None` rotates on size only
+    pub max_file_age_ms: Option<u64>,
+    /// Gzip the files, each file is only a complete gzip stream once rotated or closed
+    pub compress: bool,
+    /// Records are written to disk by the first event recorded after this interval elapses
+    pub flush_interval_ms: u64,
+}
+
+impl Default for FileRecorderConfig {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("recordings"),
+            prefix: "events".to_string(),
+            max_file_bytes: 256 * 1024 * 1024,
+            max_file_age_ms: None,
+            compress: false,
+            flush_interval_ms: 1_000,
+        }
+    }
+}
+
+enum RecordWriter {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+}
+
+impl RecordWriter {
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        match self {
+            RecordWriter::Plain(writer) => writer.write_all(buf),
+            RecordWriter::Gzip(writer) => writer.write_all(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            RecordWriter::Plain(writer) => writer.flush(),
+            RecordWriter::Gzip(writer) => writer.flush(),
+        }
+    }
+
+    /// Writes any buffered records and the gzip trailer, then syncs the file to disk
+    fn finish(self) -> io::Result<()> {
+        let mut writer = match self {
+            RecordWriter::Plain(writer) => writer,
+            RecordWriter::Gzip(writer) => writer.finish()?,
+        };
+        writer.flush()?;
+        writer.get_ref().sync_all()
+    }
+}
+
+struct RecordFile {
+    path: PathBuf,
+    writer: RecordWriter,
+    bytes: u64,
+    opened_at: Instant,
+}
+
+/// Appends every event to rotating JSON Lines files in the [`RecordedEvent`] format
+///
+/// Records are buffered and flushed every `flush_interval_ms` while events keep arriving, and
+/// the bot flushes them on shutdown. The current file is finished when the recorder is dropped.
+pub struct FileRecorder {
+    config: FileRecorderConfig,
+    file: Option<RecordFile>,
+    index: u64,
+    last_flush: Instant,
+}
+
+impl FileRecorder {
+    pub fn new(config: FileRecorderConfig) -> anyhow::Result<Self> {
+        std::fs::create_dir_all(&config.dir)?;
+
+        Ok(Self {
+            config,
+            file: None,
+            index: 0,
+            last_flush: Instant::now(),
+        })
+    }
+
+    /// Path of the file currently being written, if any
+    pub fn current_path(&self) -> Option<&PathBuf> {
+        self.file.as_ref().map(|file| &file.path)
+    }
+
+    fn needs_rotation(&self, file: &RecordFile) -> bool {
+        file.bytes >= self.config.max_file_bytes
+            || self
+                .config
+                .max_file_age_ms
+                .is_some_and(|age| file.opened_at.elapsed() >= Duration::from_millis(age))
+    }
+
+    fn open(&mut self) -> anyhow::Result<RecordFile> {
+        self.index += 1;
+        let name = format!(
+            "{}-{}-{:04}.jsonl{}",
+            self.config.prefix,
+            chrono::Utc::now().format("%Y%m%dT%H%M%S"),
+            self.index,
+            if self.config.compress { ".gz" } else { "" }
+        );
+        let path = self.config.dir.join(name);
+
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let writer = BufWriter::new(file);
+        let writer = if self.config.compress {
+            RecordWriter::Gzip(GzEncoder::new(writer, Compression::default()))
+        } else {
+            RecordWriter::Plain(writer)
+        };
+
+        tracing::info!("Recording events to {}", path.display());
+        Ok(RecordFile {
+            path,
+            writer,
+            bytes: 0,
+            opened_at: Instant::now(),
+        })
+    }
+
+    fn close(&mut self) -> anyhow::Result<()> {
+        if let Some(file) = self.file.take() {
+            file.writer.finish()?;
+            tracing::info!(
+                "Closed recording {} after {} bytes",
+                file.path.display(),
+                file.bytes
+            );
+        }
+        Ok(())
+    }
+}
+
+impl<E: Serialize> Recorder<E> for FileRecorder {
+    fn name(&self) -> &'static str {
+        "file_recorder"
+    }
+
+    fn record(&mut self, event: &E) -> anyhow::Result<()> {
+        let received_at = chrono::Utc::now().timestamp_millis() as u64;
+        let mut line = serde_json::to_vec(&RecordedEvent::new(received_at, event))?;
+        line.push(b'\n');
+
+        if self
+            .file
+            .as_ref()
+            .is_some_and(|file| self.needs_rotation(file))
+        {
+            self.close()?;
+        }
+        if self.file.is_none() {
+            self.file = Some(self.open()?);
+        }
+
+        if let Some(file) = self.file.as_mut() {
+            file.writer.write_all(&line)?;
+            file.bytes += line.len() as u64;
+        }
+
+        if self.last_flush.elapsed() >= Duration::from_millis(self.config.flush_interval_ms) {
+            <Self as Recorder<E>>::flush(self)?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> anyhow::Result<()> {
+        if let Some(file) = self.file.as_mut() {
+            file.writer.flush()?;
+        }
+        self.last_flush = Instant::now();
+        Ok(())
+    }
+}
+
+impl Drop for FileRecorder {
+    fn drop(&mut self) {
+        if let Err(e) = self.close() {
+            tracing::error!("Failed to close recording: {}", e);
+        }
+    }
+}
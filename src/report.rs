@@ -0,0 +1,796 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write as _,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use serde::Serialize;
+
+use crate::{
+    engines::book::BookKey,
+    executors::paper::Position,
+    models::{
+        EventSource, InternalEvent, OneShot, PerformanceData, StateEngine, StateOutput,
+        order::{ExecutionKind, Fill},
+    },
+};
+
+/// Positions smaller than this are considered flat, absorbing float error from partial fills
+const FLAT_QUANTITY: f64 = 1e-12;
+
+const MS_PER_YEAR: f64 = 365.0 * 24.0 * 60.0 * 60.0 * 1_000.0;
+
+#[derive(Debug, Clone)]
+pub struct PerformanceConfig {
+    /// Account value before any fill, in the quote asset, returns are measured against it
+    pub initial_equity: f64,
+    /// Spacing of equity curve points, which are also the periods Sharpe and Sortino use
+    ///
+    /// Points fall every interval from the first fill or mark, each holding the equity as of
+    /// that time, so periods without events count as periods without returns.
+    pub sample_interval_ms: u64,
+}
+
+impl Default for PerformanceConfig {
+    fn default() -> Self {
+        Self {
+            initial_equity: 10_000.0,
+            sample_interval_ms: 60_000,
+        }
+    }
+}
+
+/// PnL and trading activity of an instrument, a venue or the whole account
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PnlBreakdown {
+    pub realized_pnl: f64,
+    pub unrealized_pnl: f64,
+    pub fees: f64,
+    /// Traded notional in the quote asset
+    pub turnover: f64,
+    pub fills: usize,
+    /// Positions opened and then closed or flipped
+    pub round_trips: usize,
+    /// Fraction of round trips closed with a profit net of fees
+    pub hit_rate: f64,
+    pub avg_holding_ms: f64,
+}
+
+impl PnlBreakdown {
+    /// Net PnL after fees
+    pub fn net_pnl(&self) -> f64 {
+        self.realized_pnl + self.unrealized_pnl - self.fees
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InstrumentPerformance {
+    pub source: EventSource,
+    /// Canonical instrument name, e.g. `BTC/USDT`
+    pub instrument: String,
+    /// Signed open quantity
+    pub position: f64,
+    pub mark_price: Option<f64>,
+    #[serde(flatten)]
+    pub pnl: PnlBreakdown,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VenuePerformance {
+    pub source: EventSource,
+    #[serde(flatten)]
+    pub pnl: PnlBreakdown,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct EquityPoint {
+    pub timestamp: u64,
+    pub equity: f64,
+}
+
+/// Results of a backtest or paper session
+///
+/// Amounts of every instrument are summed as if quoted in the same asset.
+#[derive(Debug, Clone, Serialize)]
+pub struct PerformanceReport {
+    pub start: Option<u64>,
+    pub end: Option<u64>,
+    pub initial_equity: f64,
+    pub final_equity: f64,
+    pub total: PnlBreakdown,
+    pub max_drawdown: f64,
+    /// Largest decline from an equity peak, as a fraction of the peak
+    pub max_drawdown_pct: f64,
+    /// Annualized from the returns between equity curve points
+    pub sharpe: Option<f64>,
+    pub sortino: Option<f64>,
+    pub instruments: Vec<InstrumentPerformance>,
+    pub venues: Vec<VenuePerformance>,
+    pub equity_curve: Vec<EquityPoint>,
+}
+
+impl PerformanceReport {
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn export_json(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        std::fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+
+    /// Writes the per-instrument, per-venue and total breakdown to a CSV file
+    pub fn export_breakdown_csv(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let mut csv = String::from(
+            "scope,source,instrument,position,realized_pnl,unrealized_pnl,fees,net_pnl,turnover,fills,round_trips,hit_rate,avg_holding_ms\n",
+        );
+
+        let rows = self
+            .instruments
+            .iter()
+            .map(|row| {
+                (
+                    "instrument",
+                    format!("{:?}", row.source),
+                    row.instrument.clone(),
+                    row.position.to_string(),
+                    &row.pnl,
+                )
+            })
+            .chain(self.venues.iter().map(|row| {
+                (
+                    "venue",
+                    format!("{:?}", row.source),
+                    String::new(),
+                    String::new(),
+                    &row.pnl,
+                )
+            }))
+            .chain(std::iter::once((
+                "total",
+                String::new(),
+                String::new(),
+                String::new(),
+                &self.total,
+            )));
+
+        for (scope, source, instrument, position, pnl) in rows {
+            writeln!(
+                csv,
+                "{},{},{},{},{},{},{},{},{},{},{},{},{}",
+                scope,
+                source,
+                instrument,
+                position,
+                pnl.realized_pnl,
+                pnl.unrealized_pnl,
+                pnl.fees,
+                pnl.net_pnl(),
+                pnl.turnover,
+                pnl.fills,
+                pnl.round_trips,
+                pnl.hit_rate,
+                pnl.avg_holding_ms
+            )?;
+        }
+
+        std::fs::write(path, csv)?;
+        Ok(())
+    }
+
+    pub fn export_equity_csv(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let mut csv = String::from("timestamp,equity\n");
+        for point in &self.equity_curve {
+            writeln!(csv, "{},{}", point.timestamp, point.equity)?;
+        }
+
+        std::fs::write(path, csv)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+struct InstrumentStats {
+    position: Position,
+    mark: Option<f64>,
+    fees: f64,
+    turnover: f64,
+    fills: usize,
+    /// Open time and realized PnL net of fees when the current round trip started
+    open_trip: Option<(u64, f64)>,
+    round_trips: usize,
+    winning_trips: usize,
+    holding_ms: u64,
+}
+
+impl InstrumentStats {
+    fn net_realized(&self) -> f64 {
+        self.position.realized_pnl - self.fees
+    }
+
+    fn unrealized(&self) -> f64 {
+        self.mark.map_or(0.0, |mark| {
+            (mark - self.position.avg_price) * self.position.quantity
+        })
+    }
+
+    fn apply_fill(&mut self, fill: &Fill) {
+        let before = self.position.quantity;
+        let net_before = self.net_realized();
+
+        self.position.apply(fill.side, fill.price, fill.quantity);
+        self.fees += fill.fee;
+        self.turnover += fill.price * fill.quantity;
+        self.fills += 1;
+        self.mark = Some(fill.price);
+
+        let after = self.position.quantity;
+        let was_flat = before.abs() < FLAT_QUANTITY;
+        let is_flat = after.abs() < FLAT_QUANTITY;
+
+        let closed = !was_flat && (is_flat || after.signum() != before.signum());
+        if closed && let Some((opened_at, net_at_open)) = self.open_trip.take() {
+            self.round_trips += 1;
+            if self.net_realized() > net_at_open {
+                self.winning_trips += 1;
+            }
+            self.holding_ms += fill.timestamp.saturating_sub(opened_at);
+        }
+
+        if is_flat {
+            self.position = Position {
+                realized_pnl: self.position.realized_pnl,
+                ..Position::default()
+            };
+        } else if was_flat {
+            self.open_trip = Some((fill.timestamp, net_before));
+        } else if self.open_trip.is_none() {
+            // Flipped, the remainder starts a new round trip
+            self.open_trip = Some((fill.timestamp, self.net_realized()));
+        }
+    }
+
+    fn breakdown(&self) -> PnlBreakdown {
+        PnlBreakdown {
+            realized_pnl: self.position.realized_pnl,
+            unrealized_pnl: self.unrealized(),
+            fees: self.fees,
+            turnover: self.turnover,
+            fills: self.fills,
+            round_trips: self.round_trips,
+            hit_rate: ratio(self.winning_trips as f64, self.round_trips as f64),
+            avg_holding_ms: ratio(self.holding_ms as f64, self.round_trips as f64),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct PerformanceState {
+    instruments: HashMap<BookKey, InstrumentStats>,
+    equity_curve: Vec<EquityPoint>,
+    /// Equity after the last fill or mark, carried into the curve points that follow it
+    last_equity: f64,
+    start: Option<u64>,
+    end: Option<u64>,
+    peak: f64,
+    drawdown: f64,
+    max_drawdown: f64,
+    max_drawdown_pct: f64,
+}
+
+impl PerformanceState {
+    fn equity(&self, config: &PerformanceConfig) -> f64 {
+        config.initial_equity
+            + self
+                .instruments
+                .values()
+                .map(|stats| stats.net_realized() + stats.unrealized())
+                .sum::<f64>()
+    }
+
+    /// Updates the drawdown and samples the equity curve after a fill or mark at `timestamp`
+    fn update(&mut self, timestamp: u64, config: &PerformanceConfig) {
+        let equity = self.equity(config);
+        self.start.get_or_insert(timestamp);
+        self.end = Some(timestamp.max(self.end.unwrap_or(timestamp)));
+
+        self.peak = self.peak.max(equity);
+        self.drawdown = ratio(self.peak - equity, self.peak);
+        if self.peak - equity > self.max_drawdown {
+            self.max_drawdown = self.peak - equity;
+        }
+        self.max_drawdown_pct = self.max_drawdown_pct.max(self.drawdown);
+
+        let interval = config.sample_interval_ms.max(1);
+        match self.equity_curve.last() {
+            None => self.equity_curve.push(EquityPoint { timestamp, equity }),
+            Some(last) => {
+                let mut next = last.timestamp + interval;
+                while next < timestamp {
+                    self.equity_curve.push(EquityPoint {
+                        timestamp: next,
+                        equity: self.last_equity,
+                    });
+                    next += interval;
+                }
+                if next == timestamp {
+                    self.equity_curve.push(EquityPoint { timestamp, equity });
+                }
+            }
+        }
+        self.last_equity = equity;
+    }
+
+    fn report(&self, config: &PerformanceConfig) -> PerformanceReport {
+        let mut instruments = self
+            .instruments
+            .iter()
+            .map(|((source, instrument), stats)| InstrumentPerformance {
+                source: source.clone(),
+                instrument: instrument.canonical(),
+                position: stats.position.quantity,
+                mark_price: stats.mark,
+                pnl: stats.breakdown(),
+            })
+            .collect::<Vec<_>>();
+        instruments.sort_by(|a, b| {
+            (format!("{:?}", a.source), &a.instrument)
+                .cmp(&(format!("{:?}", b.source), &b.instrument))
+        });
+
+        let mut by_venue = BTreeMap::new();
+        for ((source, _), stats) in &self.instruments {
+            by_venue
+                .entry(format!("{:?}", source))
+                .or_insert_with(|| (source.clone(), Vec::new()))
+                .1
+                .push(stats);
+        }
+        let venues = by_venue
+            .into_values()
+            .map(|(source, stats)| VenuePerformance {
+                source,
+                pnl: combine(&stats),
+            })
+            .collect();
+
+        let total = combine(&self.instruments.values().collect::<Vec<_>>());
+        let periods_per_year = MS_PER_YEAR / config.sample_interval_ms.max(1) as f64;
+        let (sharpe, sortino) = risk_ratios(&self.equity_curve, periods_per_year);
+
+        PerformanceReport {
+            start: self.start,
+            end: self.end,
+            initial_equity: config.initial_equity,
+            final_equity: self.equity(config),
+            total,
+            max_drawdown: self.max_drawdown,
+            max_drawdown_pct: self.max_drawdown_pct,
+            sharpe,
+            sortino,
+            instruments,
+            venues,
+            equity_curve: self.equity_curve.clone(),
+        }
+    }
+}
+
+fn combine(stats: &[&InstrumentStats]) -> PnlBreakdown {
+    let round_trips = stats.iter().map(|s| s.round_trips).sum::<usize>();
+    let winning_trips = stats.iter().map(|s| s.winning_trips).sum::<usize>();
+    let holding_ms = stats.iter().map(|s| s.holding_ms).sum::<u64>();
+
+    PnlBreakdown {
+        realized_pnl: stats.iter().map(|s| s.position.realized_pnl).sum(),
+        unrealized_pnl: stats.iter().map(|s| s.unrealized()).sum(),
+        fees: stats.iter().map(|s| s.fees).sum(),
+        turnover: stats.iter().map(|s| s.turnover).sum(),
+        fills: stats.iter().map(|s| s.fills).sum(),
+        round_trips,
+        hit_rate: ratio(winning_trips as f64, round_trips as f64),
+        avg_holding_ms: ratio(holding_ms as f64, round_trips as f64),
+    }
+}
+
+/// Annualized Sharpe and Sortino ratios of the returns between equity curve points
+fn risk_ratios(curve: &[EquityPoint], periods_per_year: f64) -> (Option<f64>, Option<f64>) {
+    let returns = curve
+        .windows(2)
+        .filter(|pair| pair[0].equity > 0.0)
+        .map(|pair| pair[1].equity / pair[0].equity - 1.0)
+        .collect::<Vec<_>>();
+    if returns.len() < 2 {
+        return (None, None);
+    }
+
+    // Both deviations use the sample estimator, so the ratios are comparable
+    let n = returns.len() as f64;
+    let mean = returns.iter().sum::<f64>() / n;
+    let std_dev = (returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt();
+    let downside_dev = (returns.iter().map(|r| r.min(0.0).powi(2)).sum::<f64>() / (n - 1.0)).sqrt();
+
+    let annualize =
+        |deviation: f64| (deviation > 0.0).then(|| mean / deviation * periods_per_year.sqrt());
+    (annualize(std_dev), annualize(downside_dev))
+}
+
+fn ratio(numerator: f64, denominator: f64) -> f64 {
+    if denominator > 0.0 {
+        numerator / denominator
+    } else {
+        0.0
+    }
+}
+
+/// Builds a [`PerformanceReport`] from fills and mark prices seen on the bus
+///
+/// Fills are read from execution reports and trades mark open positions to market. The
+/// [`PerformanceEngine`] must be registered with the bot or backtest to receive them.
+pub struct PerformanceTracker {
+    config: PerformanceConfig,
+    state: Arc<Mutex<PerformanceState>>,
+}
+
+impl PerformanceTracker {
+    pub fn new(config: PerformanceConfig) -> Self {
+        let state = PerformanceState {
+            peak: config.initial_equity,
+            ..PerformanceState::default()
+        };
+
+        Self {
+            config,
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    /// State engine feeding the tracker, also reporting running performance to strategies
+    pub fn engine(&self) -> PerformanceEngine {
+        PerformanceEngine {
+            config: self.config.clone(),
+            state: self.state.clone(),
+        }
+    }
+
+    pub fn report(&self) -> PerformanceReport {
+        self.state
+            .lock()
+            .expect("Performance state lock poisoned")
+            .report(&self.config)
+    }
+}
+
+#[derive(Debug)]
+pub struct PerformanceEngine {
+    config: PerformanceConfig,
+    state: Arc<Mutex<PerformanceState>>,
+}
+
+#[async_trait::async_trait]
+impl StateEngine<InternalEvent, StateOutput> for PerformanceEngine {
+    fn name(&self) -> &'static str {
+        "performance_engine"
+    }
+
+    async fn sync_state(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn process_event(&mut self, event: InternalEvent) -> anyhow::Result<()> {
+        let mut state = self.state.lock().expect("Performance state lock poisoned");
+
+        match event {
+            InternalEvent::ExecutionReport(report) => {
+                if let ExecutionKind::Fill(fill) = report.kind {
+                    state
+                        .instruments
+                        .entry((fill.source.clone(), fill.instrument.clone()))
+                        .or_default()
+                        .apply_fill(&fill);
+                    state.update(fill.timestamp, &self.config);
+                }
+            }
+            InternalEvent::Trade(trade) => {
+                // Only instruments that were traded need marking
+                if let Some(stats) = state
+                    .instruments
+                    .get_mut(&(trade.source.clone(), trade.instrument.clone()))
+                {
+                    stats.mark = Some(trade.price);
+                    state.update(trade.timestamp, &self.config);
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn process_request(&self, request: OneShot<StateOutput>) -> anyhow::Result<()> {
+        let state = self.state.lock().expect("Performance state lock poisoned");
+        let total = combine(&state.instruments.values().collect::<Vec<_>>());
+        let data = PerformanceData {
+            equity: state.equity(&self.config),
+            realized_pnl: total.realized_pnl,
+            unrealized_pnl: total.unrealized_pnl,
+            fees: total.fees,
+            drawdown: state.drawdown,
+        };
+        drop(state);
+
+        request.respond(StateOutput::Performance(data))?;
+        Ok(())
+    }
+
+    fn on_shutdown(&mut self) -> anyhow::Result<()> {
+        tracing::info!("Shutting down PerformanceEngine");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::order::Side,
+        test_support::{self as support, assert_close},
+    };
+
+    fn tracker() -> (PerformanceTracker, PerformanceEngine) {
+        let tracker = PerformanceTracker::new(PerformanceConfig {
+            initial_equity: 1_000.0,
+            sample_interval_ms: 1_000,
+        });
+        let engine = tracker.engine();
+        (tracker, engine)
+    }
+
+    fn fill(engine: &mut PerformanceEngine, side: Side, price: f64, quantity: f64, at: u64) {
+        fill_with_fee(engine, side, price, quantity, 0.0, at);
+    }
+
+    fn fill_with_fee(
+        engine: &mut PerformanceEngine,
+        side: Side,
+        price: f64,
+        quantity: f64,
+        fee: f64,
+        at: u64,
+    ) {
+        let fill = support::fill("order", side, price, quantity, fee, at);
+        engine
+            .process_event(InternalEvent::ExecutionReport(support::fill_report(fill)))
+            .unwrap();
+    }
+
+    fn mark(engine: &mut PerformanceEngine, price: f64, at: u64) {
+        let trade = support::trade(EventSource::Binance, price, 1.0, at);
+        engine.process_event(InternalEvent::Trade(trade)).unwrap();
+    }
+
+    #[test]
+    fn report_without_trades_is_flat() {
+        let (tracker, mut engine) = tracker();
+        // Marks of instruments never traded are ignored
+        mark(&mut engine, 100.0, 1_000);
+
+        let report = tracker.report();
+        assert_eq!(report.start, None);
+        assert_eq!(report.total.fills, 0);
+        assert_eq!(report.total.round_trips, 0);
+        assert_close(report.total.hit_rate, 0.0);
+        assert_close(report.total.avg_holding_ms, 0.0);
+        assert_close(report.final_equity, 1_000.0);
+        assert_close(report.max_drawdown, 0.0);
+        assert_eq!(report.sharpe, None);
+        assert_eq!(report.sortino, None);
+        assert!(report.instruments.is_empty());
+        assert!(report.equity_curve.is_empty());
+    }
+
+    #[test]
+    fn round_trip_realizes_pnl_net_of_fees() {
+        let (tracker, mut engine) = tracker();
+        fill_with_fee(&mut engine, Side::Buy, 100.0, 1.0, 0.1, 0);
+        fill_with_fee(&mut engine, Side::Sell, 110.0, 1.0, 0.1, 5_000);
+
+        let total = tracker.report().total;
+        assert_close(total.realized_pnl, 10.0);
+        assert_close(total.fees, 0.2);
+        assert_close(total.net_pnl(), 9.8);
+        assert_close(total.turnover, 210.0);
+        assert_eq!(total.fills, 2);
+        assert_eq!(total.round_trips, 1);
+        assert_close(total.hit_rate, 1.0);
+        assert_close(total.avg_holding_ms, 5_000.0);
+    }
+
+    #[test]
+    fn partial_closes_complete_a_single_round_trip() {
+        let (tracker, mut engine) = tracker();
+        fill(&mut engine, Side::Buy, 100.0, 2.0, 0);
+        fill(&mut engine, Side::Sell, 105.0, 1.0, 1_000);
+        assert_eq!(tracker.report().total.round_trips, 0);
+
+        fill(&mut engine, Side::Sell, 95.0, 1.0, 2_000);
+        let total = tracker.report().total;
+        assert_eq!(total.round_trips, 1);
+        assert_close(total.realized_pnl, 0.0);
+        // Breaking even is not a win
+        assert_close(total.hit_rate, 0.0);
+    }
+
+    #[test]
+    fn hit_rate_counts_trips_won_after_fees() {
+        let (tracker, mut engine) = tracker();
+        fill(&mut engine, Side::Buy, 100.0, 1.0, 0);
+        fill(&mut engine, Side::Sell, 110.0, 1.0, 1_000);
+        fill(&mut engine, Side::Buy, 100.0, 1.0, 2_000);
+        fill(&mut engine, Side::Sell, 95.0, 1.0, 3_000);
+        // Profitable before fees only
+        fill_with_fee(&mut engine, Side::Buy, 100.0, 1.0, 1.0, 4_000);
+        fill_with_fee(&mut engine, Side::Sell, 101.0, 1.0, 1.0, 5_000);
+
+        let total = tracker.report().total;
+        assert_eq!(total.round_trips, 3);
+        assert_close(total.hit_rate, 1.0 / 3.0);
+        assert_close(total.avg_holding_ms, 1_000.0);
+    }
+
+    #[test]
+    fn flipping_the_position_starts_a_new_round_trip() {
+        let (tracker, mut engine) = tracker();
+        fill(&mut engine, Side::Buy, 100.0, 1.0, 0);
+        fill(&mut engine, Side::Sell, 110.0, 2.0, 1_000);
+
+        let report = tracker.report();
+        assert_eq!(report.total.round_trips, 1);
+        assert_close(report.instruments[0].position, -1.0);
+
+        fill(&mut engine, Side::Buy, 100.0, 1.0, 3_000);
+        let total = tracker.report().total;
+        assert_eq!(total.round_trips, 2);
+        assert_close(total.realized_pnl, 20.0);
+        assert_close(total.hit_rate, 1.0);
+        assert_close(total.avg_holding_ms, 1_500.0);
+    }
+
+    #[test]
+    fn open_positions_are_marked_to_market() {
+        let (tracker, mut engine) = tracker();
+        fill(&mut engine, Side::Buy, 100.0, 2.0, 0);
+        mark(&mut engine, 105.0, 1_000);
+
+        let report = tracker.report();
+        assert_close(report.total.unrealized_pnl, 10.0);
+        assert_close(report.final_equity, 1_010.0);
+        assert_eq!(report.instruments[0].mark_price, Some(105.0));
+    }
+
+    #[test]
+    fn drawdown_is_measured_from_the_equity_peak() {
+        let (tracker, mut engine) = tracker();
+        fill(&mut engine, Side::Buy, 100.0, 1.0, 0);
+        mark(&mut engine, 150.0, 1_000);
+        mark(&mut engine, 90.0, 2_000);
+        // A smaller decline after recovering leaves the maximum untouched
+        mark(&mut engine, 140.0, 3_000);
+        mark(&mut engine, 120.0, 4_000);
+
+        let report = tracker.report();
+        assert_close(report.max_drawdown, 60.0);
+        assert_close(report.max_drawdown_pct, 60.0 / 1_050.0);
+    }
+
+    #[test]
+    fn equity_curve_is_sampled_at_the_interval() {
+        let (tracker, mut engine) = tracker();
+        fill(&mut engine, Side::Buy, 100.0, 1.0, 0);
+        mark(&mut engine, 101.0, 500);
+        mark(&mut engine, 102.0, 1_000);
+        mark(&mut engine, 103.0, 1_999);
+        mark(&mut engine, 104.0, 2_000);
+
+        let report = tracker.report();
+        let timestamps = report
+            .equity_curve
+            .iter()
+            .map(|point| point.timestamp)
+            .collect::<Vec<_>>();
+        assert_eq!(timestamps, vec![0, 1_000, 2_000]);
+        assert_close(report.equity_curve[1].equity, 1_002.0);
+        assert_eq!((report.start, report.end), (Some(0), Some(2_000)));
+    }
+
+    #[test]
+    fn equity_curve_carries_equity_through_quiet_periods() {
+        let (tracker, mut engine) = tracker();
+        fill(&mut engine, Side::Buy, 100.0, 1.0, 0);
+        mark(&mut engine, 110.0, 1_200);
+        mark(&mut engine, 120.0, 4_500);
+
+        let report = tracker.report();
+        let points = report
+            .equity_curve
+            .iter()
+            .map(|point| (point.timestamp, point.equity))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            points,
+            vec![
+                (0, 1_000.0),
+                (1_000, 1_000.0),
+                (2_000, 1_010.0),
+                (3_000, 1_010.0),
+                (4_000, 1_010.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn flat_equity_has_no_risk_ratios() {
+        let (tracker, mut engine) = tracker();
+        fill(&mut engine, Side::Buy, 100.0, 1.0, 0);
+        for second in 1..5 {
+            mark(&mut engine, 100.0, second * 1_000);
+        }
+
+        let report = tracker.report();
+        assert_eq!(report.equity_curve.len(), 5);
+        assert_eq!(report.sharpe, None);
+        assert_eq!(report.sortino, None);
+    }
+
+    #[test]
+    fn risk_ratios_annualize_period_returns() {
+        let curve = [100.0, 110.0, 99.0, 108.9]
+            .into_iter()
+            .enumerate()
+            .map(|(index, equity)| EquityPoint {
+                timestamp: index as u64,
+                equity,
+            })
+            .collect::<Vec<_>>();
+
+        // Returns of +10%, -10% and +10%
+        let mean: f64 = 0.1 / 3.0;
+        let variance = (2.0 * (0.1 - mean).powi(2) + (-0.1 - mean).powi(2)) / 2.0;
+        let downside_variance: f64 = 0.01 / 2.0;
+        let (sharpe, sortino) = risk_ratios(&curve, 4.0);
+        assert_close(sharpe.unwrap(), mean / variance.sqrt() * 2.0);
+        assert_close(sortino.unwrap(), mean / downside_variance.sqrt() * 2.0);
+    }
+
+    #[test]
+    fn risk_ratios_need_two_returns() {
+        let curve = [
+            EquityPoint {
+                timestamp: 0,
+                equity: 100.0,
+            },
+            EquityPoint {
+                timestamp: 1,
+                equity: 110.0,
+            },
+        ];
+
+        assert_eq!(risk_ratios(&curve, 1.0), (None, None));
+    }
+
+    #[test]
+    fn gains_only_have_no_sortino() {
+        let curve = [100.0, 110.0, 130.0]
+            .into_iter()
+            .enumerate()
+            .map(|(index, equity)| EquityPoint {
+                timestamp: index as u64,
+                equity,
+            })
+            .collect::<Vec<_>>();
+
+        let (sharpe, sortino) = risk_ratios(&curve, 1.0);
+        assert!(sharpe.is_some_and(|sharpe| sharpe > 0.0));
+        assert_eq!(sortino, None);
+    }
+}
@@ -1,259 +1,1463 @@
-use std::time::Duration;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
 
+use futures::stream::FuturesUnordered;
 use tokio::{
-    sync::{broadcast, mpsc},
-    task::JoinSet,
+    sync::{
+        broadcast::{
+            self,
+            error::{RecvError, TryRecvError},
+        },
+        mpsc,
+    },
+    task::{self, JoinError, JoinSet},
 };
 use tokio_stream::StreamExt as _;
 use tokio_util::sync::CancellationToken;
 
 use crate::{
     metrics::BotMetrics,
-    models::{Collector, Executor, InputBuilder, OneShot, StateEngine, Strategy},
+    models::{
+        ActionFilter, Attributed, Collector, CollectorError, ConnectionStatus, EventPublisher,
+        Executor, InputPolicy, LagPolicy, LiveClock, OneShot, ReconnectPolicy, Recorder,
+        RegisteredStrategy, Routable, SharedClock, StateEngine, Strategy, Timestamped,
+        TriggerEvent,
+    },
+    queue::{ActionReceiver, ActionSender, Queued, action_queue},
+    schedule::EvaluationSchedule,
+    supervisor::{
+        ComponentFailure, ComponentId, ComponentKind, ComponentStatus, Monitor, Supervised,
+        Supervision, supervise,
+    },
 };
 
-/// Starts and orchestrates the entire trading bot system.
+/// Interval at which a draining bot checks whether the event bus emptied
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Runtime settings of a bot
+#[derive(Debug, Clone)]
+pub struct BotConfig {
+    /// Capacity of the event bus, receivers falling further behind lose events
+    pub event_capacity: usize,
+    /// Deadline for each state engine to sync its initial state
+    pub sync_timeout_ms: u64,
+    /// Time `BotHandle::shutdown` waits for components to exit before aborting them
+    pub shutdown_timeout_ms: u64,
+    /// Delay before each restart of a failed component, its retry limit is ignored as restarts
+    /// are bounded by the component's restart policy
+    pub restart_backoff: ReconnectPolicy,
+}
+
+impl Default for BotConfig {
+    fn default() -> Self {
+        Self {
+            event_capacity: 1_024,
+            sync_timeout_ms: 30_000,
+            shutdown_timeout_ms: 10_000,
+            restart_backoff: ReconnectPolicy::default(),
+        }
+    }
+}
+
+/// Assembles and starts the entire trading bot system.
 ///
 /// This is the main entry point that coordinates all components of the trading framework:
 /// - **State Engines**: Maintain trading state and respond to data requests
-/// - **Collectors**: Gather market data from various sources (exchanges)  
+/// - **Collectors**: Gather market data from various sources (exchanges)
 /// - **Executors**: Execute trading actions (place orders, etc.)
-/// - **Strategy**: Core trading logic that evaluates data and generates actions
+/// - **Strategies**: Core trading logic that evaluates data and generates actions, each on its own
+///   schedule against the same state engines and executors
+/// - **Recorders**: Persist every event seen on the bus, e.g. for later replay
 ///
 /// Data Flow:
 /// 1. Collectors stream market events → Broadcast to all State Engines
-/// 2. State Engines consume events and update internal state
-/// 3. Strategy wakes up periodically, requests data from State Engines via OneShot channels
+/// 2. State Engines consume events and update internal state, asking Collectors to resubscribe
+///    when they lost track of a source
+/// 3. Each Strategy wakes up every interval on the clock or when one of its triggers fires,
+///    requests data from State Engines via OneShot channels
 /// 4. State Engines respond with current data → Strategy builds input
-/// 5. Strategy evaluates input → Generates actions
-/// 6. Actions broadcast to Executors → Execute trading operations
+/// 5. Strategy evaluates input → Generates actions tagged with the strategy id
+/// 6. Actions routed to the Executors accepting them → Execute trading operations, under the
+///    queue policy of each executor
+/// 7. Executors publish outcomes (e.g. execution reports) back to State Engines via the event bus
+///
+/// The clock, wall time unless configured otherwise, observes the timestamp of every collected
+/// event, so a simulated clock follows event time during replays and strategies run on the same
+/// schedule as in production. Reconnect and restart backoff always waits on wall time, since no
+/// events move a simulated clock while a collector is down.
+///
+/// Once every collector exited, e.g. at the end of a replay, the bot drains in order and shuts
+/// down: state engines apply the remaining events, strategies stop, executors finish their queued
+/// actions and the events they published are applied. Without collectors it runs until shut
+/// down.
+///
+/// Every component runs under a [`Supervision`], deciding whether it is restarted after failing
+/// or panicking and whether the bot shuts down once it failed for good. Unless overridden, each
+/// kind of component uses [`Supervision::default_for`]. A restarted state engine syncs its state
+/// again and keeps the requests queued for it.
 ///
 /// # Type Parameters
 ///
-/// * `S` - Strategy type that implements trading logic
 /// * `E` - Event type for market data events
 /// * `D` - Data type returned by state engines
-/// * `I` - Input type consumed by strategy
-/// * `A` - Action type generated by strategy
+/// * `A` - Action type generated by strategies
 ///
-pub fn run_bot<S, E, D, I, A>(
-    strategy: S,
+pub struct BotBuilder<E, D, A> {
+    config: BotConfig,
+    strategies: Vec<RegisteredStrategy<D, A>>,
     states: Vec<Box<dyn StateEngine<E, D>>>,
     collectors: Vec<Box<dyn Collector<E>>>,
-    executors: Vec<Box<dyn Executor<A>>>,
+    executors: Vec<Box<dyn Executor<A, E>>>,
+    recorders: Vec<Box<dyn Recorder<E>>>,
+    clock: SharedClock,
     shutdown: CancellationToken,
-) -> JoinSet<()>
+    /// Supervision of every component of a kind
+    supervision: HashMap<ComponentKind, Supervision>,
+    /// Supervision of single components, by kind and name
+    component_supervision: HashMap<(ComponentKind, String), Supervision>,
+    /// Actions routed to each executor by name, replacing the filter the executor declares
+    routes: HashMap<String, ActionFilter>,
+}
+
+impl<E, D, A> Default for BotBuilder<E, D, A> {
+    fn default() -> Self {
+        Self {
+            config: BotConfig::default(),
+            strategies: Vec::new(),
+            states: Vec::new(),
+            collectors: Vec::new(),
+            executors: Vec::new(),
+            recorders: Vec::new(),
+            clock: Arc::new(LiveClock),
+            shutdown: CancellationToken::new(),
+            supervision: HashMap::new(),
+            component_supervision: HashMap::new(),
+            routes: HashMap::new(),
+        }
+    }
+}
+
+impl<E, D, A> BotBuilder<E, D, A>
 where
-    S: Strategy<D, I, A> + Send + Sync + 'static,
-    E: Clone + Send + Sync + 'static,
+    E: Timestamped + TriggerEvent + Clone + Send + Sync + 'static,
     D: Send + Sync + 'static,
-    I: Send + Sync + 'static,
-    A: Clone + Send + Sync + 'static,
+    A: Routable + Clone + Send + Sync + 'static,
 {
-    let mut set = JoinSet::new();
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-    // Broadcast channels for distributing events and actions across the system
-    let (event_tx, _) = broadcast::channel::<E>(1024);
-    let (action_tx, _) = broadcast::channel::<A>(1024);
+    pub fn with_config(mut self, config: BotConfig) -> Self {
+        self.config = config;
+        self
+    }
 
-    // Spawn executor tasks - these listen for actions and execute them
-    for executor in executors {
-        tracing::info!("Starting executor: {}", executor.name());
+    /// Registers a strategy under `id`, which must be unique within the bot
+    pub fn with_strategy<S, I>(mut self, id: &str, strategy: S) -> Self
+    where
+        S: Strategy<D, I, A> + 'static,
+        I: 'static,
+    {
+        self.strategies.push(RegisteredStrategy::new(id, strategy));
+        self
+    }
 
-        let mut action_rx = action_tx.subscribe();
-        set.spawn(async move {
-            while let Ok(action) = action_rx.recv().await {
-                if let Err(e) = executor.execute(action).await {
-                    tracing::error!(
-                        "Error executing action in executor {}: {}",
-                        executor.name(),
-                        e
-                    );
-                    BotMetrics::record_error(executor.name());
-                }
-            }
-            tracing::info!("Executor {} exited", executor.name());
-        });
+    pub fn with_state(mut self, state: impl StateEngine<E, D> + 'static) -> Self {
+        self.states.push(Box::new(state));
+        self
+    }
+
+    pub fn with_collector(mut self, collector: impl Collector<E> + 'static) -> Self {
+        self.collectors.push(Box::new(collector));
+        self
+    }
+
+    pub fn with_executor(mut self, executor: impl Executor<A, E> + 'static) -> Self {
+        self.executors.push(Box::new(executor));
+        self
+    }
+
+    pub fn with_recorder(mut self, recorder: impl Recorder<E> + 'static) -> Self {
+        self.recorders.push(Box::new(recorder));
+        self
+    }
+
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
     }
 
-    let mut request_txs = Vec::new();
+    /// Stops the bot when `shutdown` is cancelled, in addition to `BotHandle::shutdown`
+    pub fn with_shutdown(mut self, shutdown: CancellationToken) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    /// Routes the actions matching `filter` to the executor named `executor`, instead of the
+    /// actions its own filter accepts
+    pub fn with_route(mut self, executor: &str, filter: ActionFilter) -> Self {
+        self.routes.insert(executor.to_string(), filter);
+        self
+    }
+
+    /// Supervises every component of `kind` with `supervision`, unless set for the component
+    pub fn with_supervision(mut self, kind: ComponentKind, supervision: Supervision) -> Self {
+        self.supervision.insert(kind, supervision);
+        self
+    }
+
+    /// Supervises the component of `kind` named `name` with `supervision`, strategies are named
+    /// by their id
+    pub fn with_component_supervision(
+        mut self,
+        kind: ComponentKind,
+        name: &str,
+        supervision: Supervision,
+    ) -> Self {
+        self.component_supervision
+            .insert((kind, name.to_string()), supervision);
+        self
+    }
+
+    /// Spawns every component on the current Tokio runtime
+    pub fn start(self) -> BotHandle {
+        let BotBuilder {
+            config,
+            strategies,
+            states,
+            collectors,
+            executors,
+            recorders,
+            clock,
+            shutdown,
+            supervision,
+            component_supervision,
+            routes,
+        } = self;
+        // Broadcast channel for distributing events across the system
+        let (event_tx, _) = broadcast::channel::<E>(config.event_capacity);
+        BotMetrics::record_channel_capacity("events", config.event_capacity);
+
+        // Broadcast channel for state engines to send requests to the collectors
+        let (collector_request_tx, _) = broadcast::channel::<E>(config.event_capacity);
+
+        let mut handle = BotHandle::new(shutdown.clone(), &config);
+
+        // Strategies stop ahead of the other components when the bot drains
+        let strategies_shutdown = shutdown.child_token();
 
-    // Spawn state engine tasks - these maintain trading state and respond to data requests
-    for mut state in states {
-        tracing::info!("Starting state: {}", state.name());
+        // Registers a component and runs it under its supervision
+        let mut spawn = |kind: ComponentKind, name: &str, component: Box<dyn Supervised>| {
+            let supervision = component_supervision
+                .get(&(kind, name.to_string()))
+                .or_else(|| supervision.get(&kind))
+                .copied()
+                .unwrap_or_else(|| Supervision::default_for(kind));
+            let id = handle.monitor.register(kind, name);
+            let stop = match kind {
+                ComponentKind::Strategy => strategies_shutdown.clone(),
+                _ => shutdown.clone(),
+            };
 
-        // Create channel for receiving request for this state engine
-        let (request_tx, mut request_rx) = mpsc::unbounded_channel();
+            let task = supervise(
+                component,
+                id.clone(),
+                handle.monitor.clone(),
+                supervision,
+                config.restart_backoff.clone(),
+                stop,
+                shutdown.clone(),
+            );
+            handle.spawn(id, task);
+        };
 
-        // Store the request sender for the strategy to use
-        request_txs.push(request_tx);
+        let mut action_txs = Vec::new();
+
+        // Each executor task holds a sender, the channel closes once every executor exited
+        let (executors_tx, executors_rx) = mpsc::channel::<()>(1);
+
+        // Spawn executor tasks - these take actions from their own queue and execute them
+        for mut executor in executors {
+            tracing::info!("Starting executor: {}", executor.name());
+            executor.set_event_publisher(EventPublisher::new(event_tx.clone()));
+
+            let executor: Arc<dyn Executor<A, E>> = Arc::from(executor);
+            let (action_tx, action_rx) = action_queue(executor.clone());
+
+            // Store the queue for the strategies to route actions to
+            action_txs.push((executor_filter(&routes, executor.as_ref()), action_tx));
+
+            let name = executor.name();
+            let task = ExecutorTask {
+                executor,
+                action_rx,
+                _running: executors_tx.clone(),
+            };
+            spawn(ComponentKind::Executor, name, Box::new(task));
+        }
+        drop(executors_tx);
+
+        let mut request_txs = Vec::new();
+
+        // Spawn state engine tasks - these maintain trading state and respond to data requests
+        for mut state in states {
+            tracing::info!("Starting state: {}", state.name());
+            state.set_request_publisher(EventPublisher::new(collector_request_tx.clone()));
+
+            // Create channel for receiving request for this state engine
+            let (request_tx, request_rx) = mpsc::unbounded_channel();
+
+            // Store the request sender for the strategies to use
+            let name = state.name();
+            request_txs.push((name, request_tx));
+
+            let task = StateTask {
+                state,
+                request_rx,
+                event_rx: event_tx.subscribe(),
+                sync_timeout: Duration::from_millis(config.sync_timeout_ms),
+                shutdown: shutdown.clone(),
+            };
+            spawn(ComponentKind::StateEngine, name, Box::new(task));
+        }
+
+        // Spawn recorder tasks - these persist every event published on the bus
+        for recorder in recorders {
+            tracing::info!("Starting recorder: {}", recorder.name());
+
+            let name = recorder.name();
+            let task = RecorderTask {
+                recorder,
+                event_rx: event_tx.subscribe(),
+                shutdown: shutdown.clone(),
+            };
+            spawn(ComponentKind::Recorder, name, Box::new(task));
+        }
+
+        // Spawn a task per strategy - this is the core trading logic
+        for strategy in strategies {
+            tracing::info!("Starting strategy: {} ({})", strategy.id(), strategy.name());
+            let schedule = EvaluationSchedule::new(
+                strategy.interval_ms(),
+                strategy.triggers(),
+                strategy.debounce_ms(),
+            );
+            // Only strategies with triggers need to see the events themselves
+            let trigger_rx = schedule.has_triggers().then(|| event_tx.subscribe());
+
+            let id = strategy.id().to_string();
+            let task = StrategyTask {
+                strategy,
+                schedule,
+                trigger_rx,
+                request_txs: request_txs.clone(),
+                action_txs: action_txs.clone(),
+                clock: clock.clone(),
+                shutdown: strategies_shutdown.clone(),
+            };
+            spawn(ComponentKind::Strategy, &id, Box::new(task));
+        }
+        drop(action_txs);
+
+        // Each collector task holds a sender, the channel closes once every collector exited
+        let (collectors_tx, collectors_rx) = mpsc::channel::<()>(1);
+        let has_collectors = !collectors.is_empty();
+
+        // Spawn collector tasks - these gather market data from external sources and reconnect
+        // with backoff whenever their stream fails or ends
+        for collector in collectors {
+            tracing::info!("Starting collector: {}", collector.name());
+
+            let name = collector.name();
+            let task = CollectorTask {
+                collector,
+                event_tx: event_tx.clone(),
+                request_rx: collector_request_tx.subscribe(),
+                clock: clock.clone(),
+                shutdown: shutdown.clone(),
+                _running: collectors_tx.clone(),
+            };
+            spawn(ComponentKind::Collector, name, Box::new(task));
+        }
+        drop(collectors_tx);
+
+        handle.tasks.spawn(finish_after_collectors(Drain {
+            event_tx,
+            collectors_rx: has_collectors.then_some(collectors_rx),
+            executors_rx,
+            request_txs,
+            strategies_shutdown,
+            shutdown,
+        }));
+        handle
+    }
+}
+
+/// What the bot waits on to drain in order once every collector exited
+struct Drain<E, D> {
+    event_tx: broadcast::Sender<E>,
+    /// Closes once every collector exited, `None` without collectors
+    collectors_rx: Option<mpsc::Receiver<()>>,
+    /// Closes once every executor exited
+    executors_rx: mpsc::Receiver<()>,
+    request_txs: Vec<(&'static str, mpsc::UnboundedSender<OneShot<D>>)>,
+    strategies_shutdown: CancellationToken,
+    shutdown: CancellationToken,
+}
+
+/// Keeps the event bus open until shutdown, so components do not exit while the bot runs
+/// without collectors, and shuts the bot down once every collector exited, e.g. at the end of a
+/// replay. The bot first drains in order, so no event, action or execution report is cut off:
+/// 1. State engines apply every collected event
+/// 2. Strategies stop, evaluations not yet due are dropped
+/// 3. Executors finish the actions queued for them and exit
+/// 4. State engines apply the events executors and engines published meanwhile, until the bus
+///    is left empty
+async fn finish_after_collectors<E, D>(drain: Drain<E, D>) {
+    let Drain {
+        event_tx,
+        collectors_rx,
+        mut executors_rx,
+        request_txs,
+        strategies_shutdown,
+        shutdown,
+    } = drain;
+    let Some(mut collectors_rx) = collectors_rx else {
+        shutdown.cancelled().await;
+        return;
+    };
+
+    let drained = async {
+        exited(&mut collectors_rx).await;
+        tracing::info!("Every collector exited, draining the bot");
+        settle(&request_txs).await;
+
+        strategies_shutdown.cancel();
+        exited(&mut executors_rx).await;
+
+        // Applying events may publish more of them, e.g. fills of resting orders, and recorders
+        // take events at their own pace
+        settle(&request_txs).await;
+        while !event_tx.is_empty() {
+            tokio::time::sleep(DRAIN_POLL_INTERVAL).await;
+            settle(&request_txs).await;
+        }
+    };
+
+    tokio::select! {
+        _ = shutdown.cancelled() => {}
+        _ = drained => {
+            tracing::info!("Bot drained, shutting down");
+            shutdown.cancel();
+        }
+    }
+}
+
+/// Waits until every task holding a sender of `rx` exited
+async fn exited(rx: &mut mpsc::Receiver<()>) {
+    while rx.recv().await.is_some() {}
+}
+
+/// Waits until every running state engine applied the events on the bus, as engines do before
+/// answering a request
+async fn settle<D>(request_txs: &[(&'static str, mpsc::UnboundedSender<OneShot<D>>)]) {
+    let responses = request_txs
+        .iter()
+        .filter_map(|(_, sender)| {
+            let (request, rx) = OneShot::new();
+            sender.send(request).ok().map(|_| rx)
+        })
+        .collect::<Vec<_>>();
+
+    // Engines that stopped drop the request, there is nothing left for them to apply
+    futures::future::join_all(responses).await;
+}
+
+struct ExecutorTask<A, E> {
+    executor: Arc<dyn Executor<A, E>>,
+    action_rx: ActionReceiver<A>,
+    /// Dropped along with the task, lets the bot notice once every executor exited
+    _running: mpsc::Sender<()>,
+}
 
-        let mut event_rx = event_tx.subscribe();
-        let shutdown_signal = shutdown.clone();
-        set.spawn(async move {
-            if let Err(e) = state.sync_state().await {
-                tracing::error!("Failed to sync state {}: {}", state.name(), e);
-                return;
+#[async_trait::async_trait]
+impl<A, E> Supervised for ExecutorTask<A, E>
+where
+    A: Attributed + Send + Sync + 'static,
+    E: Send + 'static,
+{
+    async fn run(&mut self, id: &ComponentId, monitor: &Monitor) -> Result<(), String> {
+        let max_in_flight = self.action_rx.max_in_flight();
+        let mut in_flight = FuturesUnordered::new();
+        // Keys of the actions in flight, later actions with the same key wait for them
+        let mut in_flight_keys = HashSet::new();
+        monitor.set(id, ComponentStatus::Running);
+
+        loop {
+            // Start as many queued actions as the executor may run at once
+            while in_flight.len() < max_in_flight {
+                let Some(queued) = self.action_rx.pop_ready(&in_flight_keys) else {
+                    break;
+                };
+                if let Some(key) = &queued.key {
+                    in_flight_keys.insert(key.clone());
+                }
+                in_flight.push(execute(self.executor.clone(), queued));
             }
 
-            loop {
-                tokio::select! {
-                    biased;
-                    // Handle shutdown signal
-                    _ = shutdown_signal.cancelled() => {
-                        tracing::info!("Shutdown signal received, exiting state {}", state.name());
+            if in_flight.is_empty() && self.action_rx.is_finished() {
+                break;
+            }
 
-                        if let Err(e) = state.on_shutdown() {
-                            tracing::error!("Error during shutdown of state {}: {}", state.name(), e);
-                        }
-                        break;
+            tokio::select! {
+                Some(key) = in_flight.next(), if !in_flight.is_empty() => {
+                    if let Some(key) = key {
+                        in_flight_keys.remove(&key);
+                    }
+                }
+                _ = self.action_rx.pushed() => {}
+            }
+        }
+
+        tracing::info!("Executor {} exited", self.executor.name());
+        Ok(())
+    }
+}
+
+/// Executes a queued action, returning its key once done
+pub(crate) async fn execute<A, E>(
+    executor: Arc<dyn Executor<A, E>>,
+    queued: Queued<A>,
+) -> Option<String>
+where
+    A: Attributed,
+{
+    let strategy_id = queued.action.strategy_id().map(str::to_string);
+    if let Err(e) = executor.execute(queued.action).await {
+        tracing::error!(
+            "Error executing action from strategy {} in executor {}: {}",
+            strategy_id.as_deref().unwrap_or("unknown"),
+            executor.name(),
+            e
+        );
+        BotMetrics::record_error(executor.name());
+    }
+    queued.key
+}
+
+struct StateTask<E, D> {
+    state: Box<dyn StateEngine<E, D>>,
+    request_rx: mpsc::UnboundedReceiver<OneShot<D>>,
+    event_rx: broadcast::Receiver<E>,
+    sync_timeout: Duration,
+    shutdown: CancellationToken,
+}
+
+#[async_trait::async_trait]
+impl<E, D> Supervised for StateTask<E, D>
+where
+    E: Clone + Send + Sync + 'static,
+    D: Send + Sync + 'static,
+{
+    async fn run(&mut self, id: &ComponentId, monitor: &Monitor) -> Result<(), String> {
+        let state = &mut self.state;
+        sync(state, self.sync_timeout).await?;
+        monitor.set(id, ComponentStatus::Running);
+
+        loop {
+            BotMetrics::record_channel_queue("events", state.name(), self.event_rx.len());
+            BotMetrics::record_channel_queue("requests", state.name(), self.request_rx.len());
+
+            tokio::select! {
+                biased;
+                // Handle shutdown signal
+                _ = self.shutdown.cancelled() => {
+                    tracing::info!("Shutdown signal received, exiting state {}", state.name());
+
+                    if let Err(e) = state.on_shutdown() {
+                        tracing::error!("Error during shutdown of state {}: {}", state.name(), e);
                     }
-                    // Handle data requests from strategy (priority)
-                    request = request_rx.recv() => {
-                        match request {
-                            Some(request) => {
-                                if let Err(e) = state.process_request(request) {
-                                    tracing::error!("Error processing request in state {}: {}", state.name(), e);
-                                    BotMetrics::record_error(state.name());
+                    break;
+                }
+                // Handle data requests from strategies (priority)
+                request = self.request_rx.recv() => {
+                    match request {
+                        // The strategy stopped waiting for it, answering would only delay the next request
+                        Some(request) if request.sender.is_closed() => {
+                            tracing::debug!("Dropping expired request in state {}", state.name());
+                        }
+                        Some(request) => {
+                            // Apply the events already on the bus first, so an evaluation
+                            // triggered by an event sees it
+                            loop {
+                                match self.event_rx.try_recv() {
+                                    Ok(event) => {
+                                        if let Err(e) = state.process_event(event) {
+                                            tracing::error!("Error processing event in state {}: {}", state.name(), e);
+                                            BotMetrics::record_error(state.name());
+                                        }
+                                    }
+                                    Err(TryRecvError::Lagged(skipped)) => {
+                                        recover_from_lag(state, skipped, self.sync_timeout).await?;
+                                    }
+                                    Err(TryRecvError::Empty) | Err(TryRecvError::Closed) => break,
                                 }
                             }
-                            None => {
-                                tracing::info!("Request channel for state {} is closed, exiting", state.name());
-                                break;
+
+                            if let Err(e) = state.process_request(request) {
+                                tracing::error!("Error processing request in state {}: {}", state.name(), e);
+                                BotMetrics::record_error(state.name());
                             }
                         }
+                        None => {
+                            tracing::info!("Request channel for state {} is closed, exiting", state.name());
+                            break;
+                        }
                     }
-                    // Handle market data events (lower priority)
-                    event = event_rx.recv() => {
-                        match event {
-                            Ok(event) => {
-                                if let Err(e) = state.process_event(event) {
-                                    tracing::error!("Error processing event in state {}: {}", state.name(), e);
-                                    BotMetrics::record_error(state.name());
-                                }
-                            }
-                            Err(_) => {
-                                tracing::info!("Event channel closed for state: {}, exiting", state.name());
-                                break;
+                }
+                // Handle market data events (lower priority)
+                event = self.event_rx.recv() => {
+                    match event {
+                        Ok(event) => {
+                            if let Err(e) = state.process_event(event) {
+                                tracing::error!("Error processing event in state {}: {}", state.name(), e);
+                                BotMetrics::record_error(state.name());
                             }
                         }
+                        Err(RecvError::Lagged(skipped)) => {
+                            recover_from_lag(state, skipped, self.sync_timeout).await?;
+                        }
+                        Err(RecvError::Closed) => {
+                            tracing::info!("Event channel closed for state: {}, exiting", state.name());
+                            break;
+                        }
                     }
                 }
             }
+        }
+
+        tracing::info!("State {} exited", state.name());
+        Ok(())
+    }
+}
 
-            tracing::info!("State {} exited", state.name());
-        });
+/// Syncs the state of an engine, failing it if the sync errors or exceeds `timeout`
+async fn sync<E, D>(
+    state: &mut Box<dyn StateEngine<E, D>>,
+    timeout: Duration,
+) -> Result<(), String> {
+    match tokio::time::timeout(timeout, state.sync_state()).await {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(e)) => Err(format!("Failed to sync state: {}", e)),
+        Err(_) => Err(format!("State sync timed out after {:?}", timeout)),
+    }
+}
+
+/// Applies the lag policy of an engine that missed `skipped` events
+async fn recover_from_lag<E, D>(
+    state: &mut Box<dyn StateEngine<E, D>>,
+    skipped: u64,
+    sync_timeout: Duration,
+) -> Result<(), String> {
+    BotMetrics::record_lag("events", state.name(), skipped);
+
+    match state.lag_policy() {
+        LagPolicy::Skip => {
+            tracing::warn!(
+                "State {} lagged, {} events were skipped",
+                state.name(),
+                skipped
+            );
+            Ok(())
+        }
+        LagPolicy::Resync => {
+            tracing::warn!(
+                "State {} lagged, {} events were skipped, resyncing",
+                state.name(),
+                skipped
+            );
+            sync(state, sync_timeout).await
+        }
+        LagPolicy::Fail => Err(format!("Lagged, {} events were missed", skipped)),
     }
+}
 
-    // Spawn the main strategy task - this is the core trading logic
-    let shutdown_signal = shutdown.clone();
-    set.spawn(async move {
-        tracing::info!("Starting Strategy...");
-        let mut interval = tokio::time::interval(Duration::from_millis(strategy.interval_ms()));
+struct RecorderTask<E> {
+    recorder: Box<dyn Recorder<E>>,
+    event_rx: broadcast::Receiver<E>,
+    shutdown: CancellationToken,
+}
+
+#[async_trait::async_trait]
+impl<E> Supervised for RecorderTask<E>
+where
+    E: Clone + Send + Sync + 'static,
+{
+    async fn run(&mut self, id: &ComponentId, monitor: &Monitor) -> Result<(), String> {
+        let recorder = &mut self.recorder;
+        monitor.set(id, ComponentStatus::Running);
+
+        loop {
+            BotMetrics::record_channel_queue("events", recorder.name(), self.event_rx.len());
 
-        'strategy: loop {
             tokio::select! {
                 biased;
                 // Handle shutdown signal
-                _ = shutdown_signal.cancelled() => {
-                    tracing::info!("Shutdown signal received, exiting strategy");
-                    break 'strategy;
+                _ = self.shutdown.cancelled() => {
+                    tracing::info!("Shutdown signal received, exiting recorder {}", recorder.name());
+
+                    // Record whatever is still queued before the final flush
+                    while let Ok(event) = self.event_rx.try_recv() {
+                        if let Err(e) = recorder.record(&event) {
+                            tracing::error!("Error recording event in recorder {}: {}", recorder.name(), e);
+                            break;
+                        }
+                    }
+                    break;
                 }
-                // Periodic evaluation of strategy
-                _ = interval.tick() => {
-                    // Request current data from all state engines in parallel
-                    let mut handles = Vec::new();
-
-                    for sender in &request_txs {
-                        let (req, rx) = OneShot::new();
-                        if let Err(e) = sender.send(req) {
-                            tracing::warn!("Request channel for state is closed: {e}, exiting");
-                            break 'strategy;
+                event = self.event_rx.recv() => {
+                    match event {
+                        Ok(event) => {
+                            if let Err(e) = recorder.record(&event) {
+                                tracing::error!("Error recording event in recorder {}: {}", recorder.name(), e);
+                                BotMetrics::record_error(recorder.name());
+                            }
+                        }
+                        Err(RecvError::Lagged(skipped)) => {
+                            tracing::warn!("Recorder {} lagged, {} events were not recorded", recorder.name(), skipped);
+                            BotMetrics::record_lag("events", recorder.name(), skipped);
+                        }
+                        Err(RecvError::Closed) => {
+                            tracing::info!("Event channel closed for recorder: {}, exiting", recorder.name());
+                            break;
                         }
-
-                        handles.push(tokio::spawn(rx));
                     }
+                }
+            }
+        }
+
+        if let Err(e) = recorder.flush() {
+            tracing::error!("Error flushing recorder {}: {}", recorder.name(), e);
+        }
+        tracing::info!("Recorder {} exited", recorder.name());
+        Ok(())
+    }
+}
 
-                    // Build strategy input from collected state data
-                    let mut input_builder = S::InputBuilder::default();
-
-                    // Wait for all state responses and aggregate the data
-                    // We should not need to timeout here because state engines process events and requests both
-                    // synchronously, and requests are processed with priority over events
-                    for res in futures::future::join_all(handles).await {
-                        if let Ok(Ok(data)) = res {
-                            input_builder.insert(data);
-                        } else {
-                            tracing::error!("Error receiving data from state request, exiting");
-                            break 'strategy;
+struct StrategyTask<E, D, A> {
+    strategy: RegisteredStrategy<D, A>,
+    schedule: EvaluationSchedule,
+    trigger_rx: Option<broadcast::Receiver<E>>,
+    /// Request channel of each state engine, by engine name
+    request_txs: Vec<(&'static str, mpsc::UnboundedSender<OneShot<D>>)>,
+    /// Queue of each executor, with the filter of the actions routed to it
+    action_txs: Vec<(ActionFilter, ActionSender<A, E>)>,
+    clock: SharedClock,
+    shutdown: CancellationToken,
+}
+
+#[async_trait::async_trait]
+impl<E, D, A> Supervised for StrategyTask<E, D, A>
+where
+    E: TriggerEvent + Clone + Send + Sync + 'static,
+    D: Send + Sync + 'static,
+    A: Routable + Clone + Send + Sync + 'static,
+{
+    async fn run(&mut self, id: &ComponentId, monitor: &Monitor) -> Result<(), String> {
+        let strategy = &self.strategy;
+        let schedule = &mut self.schedule;
+        monitor.set(id, ComponentStatus::Running);
+        schedule.start_at(self.clock.now_ms());
+
+        loop {
+            let due = schedule.next_due();
+            tokio::select! {
+                biased;
+                // Handle shutdown signal
+                _ = self.shutdown.cancelled() => {
+                    tracing::info!("Shutdown signal received, exiting strategy {}", strategy.id());
+                    break;
+                }
+                // Evaluation due on the interval or after a trigger fired
+                _ = self.clock.sleep_until(due.unwrap_or(u64::MAX)), if due.is_some() => {}
+                // Market activity matched against the strategy triggers
+                event = next_trigger_event(&mut self.trigger_rx) => {
+                    match event {
+                        Ok(event) => {
+                            schedule.observe(&event);
+                        }
+                        Err(RecvError::Lagged(skipped)) => {
+                            // Any of the missed events may have fired a trigger
+                            tracing::warn!("Strategy {} lagged, {} events were not matched against triggers", strategy.id(), skipped);
+                            BotMetrics::record_lag("events", strategy.id(), skipped);
+                            schedule.trigger();
+                        }
+                        Err(RecvError::Closed) => {
+                            tracing::info!("Event channel closed for strategy {}, exiting", strategy.id());
+                            break;
                         }
                     }
+                    continue;
+                }
+            }
+            schedule.evaluated(self.clock.now_ms());
 
-                    // Build the final input for strategy evaluation
-                    let input = match input_builder.build() {
-                        Ok(input) => input,
-                        Err(e) => {
-                            tracing::error!("Error building input: {}, skipping", e);
-                            BotMetrics::record_error("strategy");
-                            continue;
-                        }
-                    };
+            // Request current data from all state engines in parallel, each within the deadline
+            // State engines apply the events already on the bus before responding, so the input
+            // reflects the event that triggered the evaluation
+            let deadline = Duration::from_millis(strategy.request_timeout_ms());
+            let requests = self
+                .request_txs
+                .iter()
+                .map(|(engine, sender)| request_state(sender, engine, deadline));
+            let responses = futures::future::join_all(requests).await;
+
+            // Aggregate the data, keeping track of the engines that failed to respond
+            let mut data = Vec::with_capacity(responses.len());
+            let mut missing = Vec::new();
+            for ((engine, _), response) in self.request_txs.iter().zip(responses) {
+                match response {
+                    Ok(response) => data.push(response),
+                    Err(e) => {
+                        tracing::warn!("State {} {} for strategy {}", engine, e, strategy.id());
+                        BotMetrics::record_state_request_failure(engine, strategy.id());
+                        missing.push(*engine);
+                    }
+                }
+            }
 
-                    // Run strategy logic to generate trading actions
-                    let actions = strategy.evaluate(input);
+            if !missing.is_empty() && strategy.input_policy() == InputPolicy::Complete {
+                tracing::warn!(
+                    "Missing input from {} for strategy {}, skipping",
+                    missing.join(", "),
+                    strategy.id()
+                );
+                continue;
+            }
 
-                    // Send all generated actions to executors
-                    for action in actions {
-                        if action_tx.send(action).is_err() {
-                            tracing::error!("Action channel is closed, exiting");
-                            break 'strategy;
-                        }
+            // Build the strategy input and run the strategy logic to generate trading actions
+            let actions = match strategy.evaluate(data, &missing) {
+                Ok(actions) => actions,
+                Err(e) => {
+                    tracing::error!(
+                        "Error building input for strategy {}: {}, skipping",
+                        strategy.id(),
+                        e
+                    );
+                    BotMetrics::record_error(strategy.id());
+                    continue;
+                }
+            };
+            BotMetrics::record_strategy_evaluation(strategy.id(), actions.len());
+
+            // Route all generated actions to executors, tagged with the strategy that emitted them
+            for action in actions {
+                for (action_tx, action) in route(strategy.id(), action, &self.action_txs) {
+                    if let Err(action) = action_tx.push(action).await {
+                        tracing::warn!(
+                            "Executor {} is no longer running, dropped action from strategy {}",
+                            action_tx.name(),
+                            strategy.id()
+                        );
+                        BotMetrics::record_action_dropped(action_tx.name(), "closed");
+                        action_tx.discard(&action);
                     }
                 }
             }
         }
 
-        tracing::info!("Strategy exited");
-    });
+        tracing::info!("Strategy {} exited", strategy.id());
+        Ok(())
+    }
+}
 
-    // Spawn collector tasks - these gather market data from external sources
-    for collector in collectors {
-        tracing::info!("Starting collector: {}", collector.name());
-        let shutdown_signal = shutdown.clone();
-        let event_tx = event_tx.clone();
+/// Filter of the actions routed to an executor, its routing table entry or else its own filter
+pub(crate) fn executor_filter<A, E>(
+    routes: &HashMap<String, ActionFilter>,
+    executor: &dyn Executor<A, E>,
+) -> ActionFilter {
+    routes
+        .get(executor.name())
+        .cloned()
+        .unwrap_or_else(|| executor.action_filter())
+}
 
-        set.spawn(async move {
-            let mut stream = collector.get_event_stream().await.unwrap();
+/// Tags an action with the strategy that emitted it and registers it with every executor whose
+/// filter accepts it, returning the queues to push each registered copy onto. Counts the action
+/// as unroutable when no filter accepts it
+pub(crate) fn route<'a, A, E>(
+    strategy_id: &str,
+    mut action: A,
+    action_txs: &'a [(ActionFilter, ActionSender<A, E>)],
+) -> Vec<(&'a ActionSender<A, E>, A)>
+where
+    A: Routable + Clone,
+{
+    action.set_strategy_id(strategy_id);
+
+    let mut routed = Vec::new();
+    let mut accepted = false;
+    for (filter, action_tx) in action_txs {
+        if !filter.matches(&action) {
+            continue;
+        }
+        accepted = true;
 
-            loop {
+        let mut registered = action.clone();
+        match action_tx.register(&mut registered) {
+            Ok(()) => routed.push((action_tx, registered)),
+            Err(e) => {
+                tracing::warn!(
+                    "Executor {} refused {} action from strategy {}: {}",
+                    action_tx.name(),
+                    action.action_type(),
+                    strategy_id,
+                    e
+                );
+                BotMetrics::record_action_dropped(action_tx.name(), "refused");
+            }
+        }
+    }
+
+    if !accepted {
+        tracing::warn!(
+            "No executor accepts {} action from strategy {}, dropped it",
+            action.action_type(),
+            strategy_id
+        );
+        BotMetrics::record_unroutable_action(strategy_id, &action.action_type());
+    }
+    routed
+}
+
+struct CollectorTask<E> {
+    collector: Box<dyn Collector<E>>,
+    event_tx: broadcast::Sender<E>,
+    request_rx: broadcast::Receiver<E>,
+    /// Dropped along with the task, lets the bot notice once every collector exited
+    _running: mpsc::Sender<()>,
+    clock: SharedClock,
+    shutdown: CancellationToken,
+}
+
+#[async_trait::async_trait]
+impl<E> Supervised for CollectorTask<E>
+where
+    E: Timestamped + Clone + Send + Sync + 'static,
+{
+    async fn run(&mut self, id: &ComponentId, monitor: &Monitor) -> Result<(), String> {
+        let collector = &self.collector;
+        let policy = collector.reconnect_policy();
+        let mut attempt = 0;
+        let mut requests_closed = false;
+        let mut resync = false;
+
+        'collector: loop {
+            // Wait out the initial backoff before resubscribing, so a feed that keeps gapping
+            // is not resubscribed in a tight loop. Backoff runs on wall time, as a simulated
+            // clock only moves with the events a disconnected collector no longer produces
+            if std::mem::take(&mut resync) {
                 tokio::select! {
                     biased;
-                    // Handle shutdown signal
-                    _ = shutdown_signal.cancelled() => {
+                    _ = self.shutdown.cancelled() => {
                         tracing::info!("Shutdown signal received, exiting collector {}", collector.name());
                         break;
                     }
-                    // Collect market data events
-                    event = stream.next() => {
-                        match event {
-                            Some(event) => {
-                                if event_tx.send(event).is_err() {
-                                    tracing::info!("Internal event channel is closed, exiting collector {}", collector.name());
-                                    break;
+                    _ = tokio::time::sleep(policy.delay(0)) => {}
+                }
+            }
+
+            let error = match collector.get_event_stream().await {
+                Ok(mut stream) => {
+                    monitor.set(id, ComponentStatus::Running);
+                    if attempt > 0 {
+                        tracing::info!("Collector {} reconnected", collector.name());
+                        if let Some(event) =
+                            collector.connection_event(ConnectionStatus::Reconnected)
+                        {
+                            let _ = self.event_tx.send(event);
+                        }
+                    }
+                    attempt = 0;
+
+                    loop {
+                        tokio::select! {
+                            biased;
+                            // Handle shutdown signal
+                            _ = self.shutdown.cancelled() => {
+                                tracing::info!("Shutdown signal received, exiting collector {}", collector.name());
+                                break 'collector;
+                            }
+                            // Resubscribe when a state engine lost track of the source
+                            request = self.request_rx.recv(), if !requests_closed => {
+                                match request {
+                                    Ok(request) if collector.resync_requested(&request) => {
+                                        tracing::info!("Resync requested, resubscribing collector {}", collector.name());
+                                        BotMetrics::record_reconnect(collector.name());
+                                        resync = true;
+                                        continue 'collector;
+                                    }
+                                    // No state engine is left to send requests
+                                    Err(RecvError::Closed) => requests_closed = true,
+                                    _ => {}
                                 }
                             }
-                            None => {
-                                tracing::info!("Collector {} stream ended, exiting", collector.name());
-                                break;
+                            // Collect market data events
+                            event = stream.next() => {
+                                match event {
+                                    Some(event) => {
+                                        if let Some(timestamp) = event.timestamp() {
+                                            self.clock.observe(timestamp);
+                                        }
+                                        if self.event_tx.send(event).is_err() {
+                                            tracing::info!("Internal event channel is closed, exiting collector {}", collector.name());
+                                            break 'collector;
+                                        }
+                                    }
+                                    // Finite streams, such as replays, complete when they end
+                                    None if collector.is_finite() => {
+                                        tracing::info!("Collector {} finished its stream", collector.name());
+                                        break 'collector;
+                                    }
+                                    None => break CollectorError::StreamClosed,
+                                }
                             }
                         }
                     }
                 }
+                Err(e) => e,
+            };
+
+            // Report the failure, and let state engines know the source is unavailable once rather
+            // than on every failed reconnect attempt
+            tracing::error!("Collector {} disconnected: {}", collector.name(), error);
+            BotMetrics::record_error(collector.name());
+            let reason = error.to_string();
+            if attempt == 0
+                && let Some(event) =
+                    collector.connection_event(ConnectionStatus::Disconnected(error))
+            {
+                let _ = self.event_tx.send(event);
             }
 
-            tracing::info!("Collector {} exited", collector.name());
-        });
+            if !policy.should_retry(attempt) {
+                return Err(format!("Exhausted reconnect attempts: {}", reason));
+            }
+
+            let delay = policy.delay(attempt);
+            attempt += 1;
+            tracing::info!(
+                "Reconnecting collector {} in {:?} (attempt {})",
+                collector.name(),
+                delay,
+                attempt
+            );
+            BotMetrics::record_reconnect(collector.name());
+
+            tokio::select! {
+                biased;
+                _ = self.shutdown.cancelled() => {
+                    tracing::info!("Shutdown signal received, exiting collector {}", collector.name());
+                    break;
+                }
+                _ = tokio::time::sleep(delay) => {}
+            }
+        }
+
+        tracing::info!("Collector {} exited", collector.name());
+        Ok(())
+    }
+}
+
+/// Controls a running bot and reports the state of its components
+#[derive(Debug)]
+pub struct BotHandle {
+    tasks: JoinSet<()>,
+    /// Component run by each task, removed once the task is joined
+    components: HashMap<task::Id, ComponentId>,
+    monitor: Monitor,
+    failures: mpsc::UnboundedReceiver<ComponentFailure>,
+    shutdown: CancellationToken,
+    shutdown_timeout: Duration,
+}
+
+impl BotHandle {
+    fn new(shutdown: CancellationToken, config: &BotConfig) -> Self {
+        let (monitor, failures) = Monitor::new();
+        Self {
+            tasks: JoinSet::new(),
+            components: HashMap::new(),
+            monitor,
+            failures,
+            shutdown,
+            shutdown_timeout: Duration::from_millis(config.shutdown_timeout_ms),
+        }
+    }
+
+    /// Token cancelled on shutdown, e.g. to stop other tasks along with the bot
+    pub fn shutdown_token(&self) -> CancellationToken {
+        self.shutdown.clone()
+    }
+
+    /// Current status of every component
+    pub fn status(&self) -> BTreeMap<ComponentId, ComponentStatus> {
+        self.monitor.statuses()
+    }
+
+    /// Waits for the first component to fail for good, `None` once every component exited
+    /// without failing
+    ///
+    /// A component that panics or fails is only reported once its restart policy gives up on it.
+    pub async fn failure(&mut self) -> Option<ComponentFailure> {
+        loop {
+            if self.tasks.is_empty() {
+                return self.failures.try_recv().ok();
+            }
+
+            tokio::select! {
+                biased;
+                Some(failure) = self.failures.recv() => return Some(failure),
+                Some(joined) = self.tasks.join_next_with_id() => self.on_joined(joined),
+            }
+        }
+    }
+
+    /// Waits for every component to exit
+    pub async fn join(&mut self) {
+        while let Some(joined) = self.tasks.join_next_with_id().await {
+            self.on_joined(joined);
+        }
+    }
+
+    /// Signals every component to stop and waits for them, aborting those still running after
+    /// the shutdown timeout
+    pub async fn shutdown(&mut self) {
+        self.shutdown.cancel();
+
+        if tokio::time::timeout(self.shutdown_timeout, self.join())
+            .await
+            .is_err()
+        {
+            tracing::warn!(
+                "Components still running after {:?}, aborting them",
+                self.shutdown_timeout
+            );
+            self.tasks.abort_all();
+            self.join().await;
+        }
+    }
+
+    fn spawn(&mut self, id: ComponentId, task: impl Future<Output = ()> + Send + 'static) {
+        let handle = self.tasks.spawn(task);
+        self.components.insert(handle.id(), id);
     }
 
-    set
+    fn on_joined(&mut self, joined: Result<(task::Id, ()), JoinError>) {
+        match joined {
+            Ok((task_id, ())) => {
+                self.components.remove(&task_id);
+            }
+            Err(e) => {
+                let Some(id) = self.components.remove(&e.id()) else {
+                    return;
+                };
+                // Panics are caught by the supervisor, so the task was aborted on shutdown
+                self.monitor.set(&id, ComponentStatus::Stopped);
+            }
+        }
+    }
+}
+
+/// Requests the current data of a state engine, failing if it does not respond within `deadline`
+///
+/// A slow or restarting engine costs the strategy an evaluation, not the whole strategy.
+async fn request_state<D>(
+    sender: &mpsc::UnboundedSender<OneShot<D>>,
+    engine: &str,
+    deadline: Duration,
+) -> Result<D, String> {
+    let started = std::time::Instant::now();
+    let (request, rx) = OneShot::new();
+    sender
+        .send(request)
+        .map_err(|_| "is no longer running".to_string())?;
+
+    match tokio::time::timeout(deadline, rx).await {
+        Ok(Ok(response)) => {
+            BotMetrics::record_state_request(engine, started.elapsed());
+            Ok(response)
+        }
+        Ok(Err(_)) => Err("dropped the request".to_string()),
+        Err(_) => Err(format!("did not respond within {:?}", deadline)),
+    }
+}
+
+/// Next event for the strategy triggers, pending forever for strategies without triggers
+async fn next_trigger_event<E: Clone>(
+    rx: &mut Option<broadcast::Receiver<E>>,
+) -> Result<E, RecvError> {
+    match rx {
+        Some(rx) => rx.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Mutex,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    };
+
+    use tokio::sync::Notify;
+
+    use super::*;
+    use crate::models::{
+        CollectorStream, EventSource, InputBuilder, InternalEvent, SimulatedClock, StateOutput,
+    };
+
+    /// Collector failing its first connection attempts, then streaming nothing until shut down
+    struct Flaky {
+        failures: usize,
+        connects: Arc<AtomicUsize>,
+    }
+
+    #[async_trait::async_trait]
+    impl Collector<InternalEvent> for Flaky {
+        fn name(&self) -> &'static str {
+            "flaky"
+        }
+
+        async fn get_event_stream(
+            &self,
+        ) -> Result<CollectorStream<'_, InternalEvent>, CollectorError> {
+            if self.connects.fetch_add(1, Ordering::SeqCst) < self.failures {
+                return Err(CollectorError::StreamClosed);
+            }
+            Ok(Box::pin(tokio_stream::pending()))
+        }
+
+        fn reconnect_policy(&self) -> ReconnectPolicy {
+            ReconnectPolicy {
+                initial_delay_ms: 5,
+                jitter: 0.0,
+                ..Default::default()
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn collectors_reconnect_while_a_simulated_clock_stands_still() {
+        let connects = Arc::new(AtomicUsize::new(0));
+        let mut bot = BotBuilder::<InternalEvent, StateOutput, String>::new()
+            .with_clock(Arc::new(SimulatedClock::new(0)))
+            .with_collector(Flaky {
+                failures: 2,
+                connects: connects.clone(),
+            })
+            .start();
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while connects.load(Ordering::SeqCst) < 3 {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("collector did not reconnect");
+        bot.shutdown().await;
+    }
+
+    /// Finite collector sending a few trades, and ending once the strategy emitted its actions
+    struct Replay {
+        emitted: Arc<Notify>,
+    }
+
+    #[async_trait::async_trait]
+    impl Collector<InternalEvent> for Replay {
+        fn name(&self) -> &'static str {
+            "replay"
+        }
+
+        async fn get_event_stream(
+            &self,
+        ) -> Result<CollectorStream<'_, InternalEvent>, CollectorError> {
+            let trades = (0..3).map(|i| {
+                InternalEvent::Trade(crate::test_support::trade(
+                    EventSource::Binance,
+                    100.0,
+                    1.0,
+                    i,
+                ))
+            });
+            let emitted = self.emitted.clone();
+            let end =
+                futures::stream::once(async move { emitted.notified().await }).filter_map(|_| None);
+            Ok(Box::pin(tokio_stream::iter(trades).chain(end)))
+        }
+
+        fn is_finite(&self) -> bool {
+            true
+        }
+    }
+
+    #[derive(Default)]
+    struct Unit;
+
+    impl InputBuilder<StateOutput, ()> for Unit {
+        fn insert(&mut self, _data: StateOutput) {}
+
+        fn build(self) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Emits three actions on its first evaluation
+    struct Burst {
+        fired: AtomicBool,
+        emitted: Arc<Notify>,
+    }
+
+    impl Strategy<StateOutput, (), String> for Burst {
+        type InputBuilder = Unit;
+
+        fn name(&self) -> &'static str {
+            "burst"
+        }
+
+        fn interval_ms(&self) -> u64 {
+            1
+        }
+
+        fn evaluate(&self, _input: ()) -> Vec<String> {
+            if self.fired.swap(true, Ordering::SeqCst) {
+                return Vec::new();
+            }
+            self.emitted.notify_one();
+            (0..3).map(|i| i.to_string()).collect()
+        }
+    }
+
+    /// Executes actions slowly, publishing each one back onto the bus
+    #[derive(Default)]
+    struct Slow {
+        executed: Arc<Mutex<Vec<String>>>,
+        publisher: Option<EventPublisher<InternalEvent>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Executor<String, InternalEvent> for Slow {
+        fn name(&self) -> &'static str {
+            "slow"
+        }
+
+        async fn execute(&self, action: String) -> anyhow::Result<()> {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            self.executed.lock().unwrap().push(action.clone());
+            self.publisher
+                .as_ref()
+                .unwrap()
+                .publish(InternalEvent::Unsupported(action))
+        }
+
+        fn set_event_publisher(&mut self, publisher: EventPublisher<InternalEvent>) {
+            self.publisher = Some(publisher);
+        }
+    }
+
+    /// Counts the events executors published
+    struct Feedback(Arc<AtomicUsize>);
+
+    #[async_trait::async_trait]
+    impl StateEngine<InternalEvent, StateOutput> for Feedback {
+        fn name(&self) -> &'static str {
+            "feedback"
+        }
+
+        async fn sync_state(&mut self) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        fn process_event(&mut self, event: InternalEvent) -> anyhow::Result<()> {
+            if let InternalEvent::Unsupported(_) = event {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+            Ok(())
+        }
+
+        fn process_request(&self, request: OneShot<StateOutput>) -> anyhow::Result<()> {
+            request.respond(StateOutput::Prices(Vec::new()))
+        }
+
+        fn on_shutdown(&mut self) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn bot_drains_queued_actions_and_feedback_once_collectors_finish() {
+        let emitted = Arc::new(Notify::new());
+        let executor = Slow::default();
+        let executed = executor.executed.clone();
+        let feedback = Arc::new(AtomicUsize::new(0));
+
+        let mut bot = BotBuilder::new()
+            .with_collector(Replay {
+                emitted: emitted.clone(),
+            })
+            .with_strategy(
+                "burst",
+                Burst {
+                    fired: AtomicBool::new(false),
+                    emitted,
+                },
+            )
+            .with_state(Feedback(feedback.clone()))
+            .with_executor(executor)
+            .start();
+
+        let failure = tokio::time::timeout(Duration::from_secs(5), bot.failure())
+            .await
+            .expect("bot did not finish");
+        assert!(failure.is_none());
+        assert_eq!(*executed.lock().unwrap(), vec!["0", "1", "2"]);
+        assert_eq!(feedback.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn live_collectors_without_retries_fail_when_their_stream_ends() {
+        struct Once;
+
+        #[async_trait::async_trait]
+        impl Collector<InternalEvent> for Once {
+            fn name(&self) -> &'static str {
+                "once"
+            }
+
+            async fn get_event_stream(
+                &self,
+            ) -> Result<CollectorStream<'_, InternalEvent>, CollectorError> {
+                Ok(Box::pin(tokio_stream::empty()))
+            }
+
+            fn reconnect_policy(&self) -> ReconnectPolicy {
+                ReconnectPolicy {
+                    max_retries: Some(0),
+                    ..Default::default()
+                }
+            }
+        }
+
+        let mut bot = BotBuilder::<InternalEvent, StateOutput, String>::new()
+            .with_collector(Once)
+            .start();
+
+        let failure = tokio::time::timeout(Duration::from_secs(5), bot.failure())
+            .await
+            .expect("collector did not fail");
+        assert_eq!(failure.unwrap().component.kind, ComponentKind::Collector);
+        bot.shutdown().await;
+    }
 }
@@ -0,0 +1,179 @@
+use crate::models::{Strategy, Trigger, TriggerEvent, TriggerState};
+
+/// Decides when a strategy is evaluated, from its interval and its triggers
+///
+/// Interval ticks keep a fixed schedule, ticks missed while evaluating are skipped rather than
+/// replayed. Triggers firing within the debounce of the last evaluation are coalesced into a
+/// single evaluation once the debounce has passed. Times are in milliseconds on the clock the
+/// caller evaluates on.
+#[derive(Debug)]
+pub struct EvaluationSchedule {
+    interval_ms: u64,
+    next_tick: Option<u64>,
+    triggers: TriggerState,
+    debounce_ms: u64,
+    last_evaluation: Option<u64>,
+    /// Whether a trigger fired since the last evaluation
+    pending: bool,
+}
+
+impl EvaluationSchedule {
+    pub fn new(interval_ms: u64, triggers: Vec<Trigger>, debounce_ms: u64) -> Self {
+        Self {
+            interval_ms,
+            next_tick: None,
+            triggers: TriggerState::new(triggers),
+            debounce_ms,
+            last_evaluation: None,
+            pending: false,
+        }
+    }
+
+    pub fn for_strategy<S, D, I, A>(strategy: &S) -> Self
+    where
+        S: Strategy<D, I, A>,
+    {
+        Self::new(
+            strategy.interval_ms(),
+            strategy.triggers(),
+            strategy.debounce_ms(),
+        )
+    }
+
+    /// Schedules the first interval tick, a no-op for trigger-only schedules or once started
+    pub fn start_at(&mut self, first_tick: u64) {
+        if self.interval_ms > 0 && self.next_tick.is_none() {
+            self.next_tick = Some(first_tick);
+        }
+    }
+
+    pub fn has_triggers(&self) -> bool {
+        !self.triggers.is_empty()
+    }
+
+    /// Updates the trigger state with an event, returning whether a trigger fired
+    pub fn observe<E: TriggerEvent>(&mut self, event: &E) -> bool {
+        let fired = self.triggers.observe(event);
+        self.pending |= fired;
+        fired
+    }
+
+    /// Whether a trigger fired since the last evaluation
+    pub fn is_pending(&self) -> bool {
+        self.pending
+    }
+
+    /// Requests an evaluation as if a trigger fired, e.g. after events were missed
+    pub fn trigger(&mut self) {
+        if !self.triggers.is_empty() {
+            self.pending = true;
+        }
+    }
+
+    /// Time of the next evaluation, `None` if nothing is scheduled
+    pub fn next_due(&self) -> Option<u64> {
+        let triggered = self.pending.then(|| {
+            self.last_evaluation
+                .map_or(0, |last| last + self.debounce_ms)
+        });
+
+        match (self.next_tick, triggered) {
+            (Some(tick), Some(triggered)) => Some(tick.min(triggered)),
+            (tick, triggered) => tick.or(triggered),
+        }
+    }
+
+    /// Records an evaluation at `now`, whatever caused it
+    pub fn evaluated(&mut self, now: u64) {
+        if let Some(tick) = self.next_tick.as_mut()
+            && *tick <= now
+        {
+            *tick += self.interval_ms;
+            if *tick <= now {
+                *tick = now + self.interval_ms;
+            }
+        }
+
+        self.last_evaluation = Some(now);
+        self.pending = false;
+        self.triggers.evaluated();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{EventSource, InternalEvent},
+        test_support as support,
+    };
+
+    fn event() -> InternalEvent {
+        InternalEvent::Trade(support::trade(EventSource::Binance, 100.0, 1.0, 0))
+    }
+
+    #[test]
+    fn interval_ticks_keep_a_fixed_schedule() {
+        let mut schedule = EvaluationSchedule::new(1_000, Vec::new(), 0);
+        assert_eq!(schedule.next_due(), None);
+
+        schedule.start_at(1_000);
+        schedule.start_at(5_000);
+        assert_eq!(schedule.next_due(), Some(1_000));
+
+        // A late evaluation keeps the next tick on schedule
+        schedule.evaluated(1_200);
+        assert_eq!(schedule.next_due(), Some(2_000));
+
+        // Ticks missed entirely are skipped rather than replayed
+        schedule.evaluated(4_500);
+        assert_eq!(schedule.next_due(), Some(5_500));
+    }
+
+    #[test]
+    fn triggers_are_debounced_from_the_last_evaluation() {
+        let mut schedule = EvaluationSchedule::new(0, vec![Trigger::EveryEvents(1)], 500);
+        schedule.start_at(1_000);
+        assert_eq!(schedule.next_due(), None);
+
+        assert!(schedule.observe(&event()));
+        assert_eq!(schedule.next_due(), Some(0));
+        schedule.evaluated(100);
+        assert!(!schedule.is_pending());
+        assert_eq!(schedule.next_due(), None);
+
+        // Triggers firing within the debounce coalesce into one evaluation once it passed
+        schedule.observe(&event());
+        schedule.observe(&event());
+        assert_eq!(schedule.next_due(), Some(600));
+        schedule.evaluated(600);
+        assert_eq!(schedule.next_due(), None);
+    }
+
+    #[test]
+    fn the_earlier_of_tick_and_trigger_is_due() {
+        let mut schedule = EvaluationSchedule::new(1_000, vec![Trigger::EveryEvents(2)], 200);
+        schedule.start_at(1_000);
+        schedule.evaluated(1_000);
+
+        assert!(!schedule.observe(&event()));
+        assert_eq!(schedule.next_due(), Some(2_000));
+        assert!(schedule.observe(&event()));
+        assert_eq!(schedule.next_due(), Some(1_200));
+
+        // An interval evaluation also resolves the pending trigger
+        schedule.evaluated(1_200);
+        assert_eq!(schedule.next_due(), Some(2_000));
+    }
+
+    #[test]
+    fn manual_triggers_need_triggers_configured() {
+        let mut schedule = EvaluationSchedule::new(1_000, Vec::new(), 0);
+        schedule.trigger();
+        assert!(!schedule.is_pending());
+
+        let mut schedule = EvaluationSchedule::new(0, vec![Trigger::EveryEvents(10)], 0);
+        schedule.trigger();
+        assert!(schedule.is_pending());
+    }
+}
@@ -15,7 +15,9 @@ pub struct EchoInputBuilder {
 
 impl InputBuilder<StateOutput, EchoInput> for EchoInputBuilder {
     fn insert(&mut self, data: StateOutput) {
-        self.state_output = Some(data);
+        if let StateOutput::Prices(_) = data {
+            self.state_output = Some(data);
+        }
     }
 
     fn build(self) -> Result<EchoInput, anyhow::Error> {
@@ -40,7 +42,12 @@ impl Strategy<StateOutput, EchoInput, String> for EchoStrategy {
         input
             .prices
             .iter()
-            .map(|price_data| format!("{:?}: {}", price_data.source, price_data))
+            .map(|price_data| {
+                format!(
+                    "{:?} {}: {}",
+                    price_data.source, price_data.instrument, price_data
+                )
+            })
             .collect()
     }
 }
@@ -0,0 +1,495 @@
+use std::{
+    any::Any,
+    collections::BTreeMap,
+    panic::AssertUnwindSafe,
+    sync::{Arc, Mutex},
+};
+
+use futures::FutureExt as _;
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+use crate::{metrics::BotMetrics, models::ReconnectPolicy};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ComponentKind {
+    Collector,
+    StateEngine,
+    Strategy,
+    Executor,
+    Recorder,
+}
+
+/// A running component, named after the component or, for strategies, their id
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ComponentId {
+    pub kind: ComponentKind,
+    pub name: String,
+}
+
+impl std::fmt::Display for ComponentId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} {}", self.kind, self.name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ComponentStatus {
+    Starting,
+    Running,
+    /// Waiting to be restarted after failing
+    Restarting {
+        restarts: u32,
+        error: String,
+    },
+    /// Exited on shutdown or after completing its work
+    Stopped,
+    /// Exited because of an error it could not recover from
+    Failed(String),
+}
+
+/// Failure that stopped a component for good
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComponentFailure {
+    pub component: ComponentId,
+    pub error: String,
+}
+
+impl std::fmt::Display for ComponentFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} failed: {}", self.component, self.error)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestartPolicy {
+    /// Leave the component stopped once it exits
+    Never,
+    /// Restart the component after it fails, up to `max_restarts` times over the bot's lifetime
+    OnFailure { max_restarts: u32 },
+    /// Restart the component after every failure, without limit
+    Always,
+    /// Restart the component whenever it exits before shutdown, failed or not, for components
+    /// that must run until shutdown
+    OnExit,
+}
+
+/// What happens to the rest of the bot once a component has failed for good
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Escalation {
+    /// Keep the other components running
+    Isolate,
+    /// Shut the whole bot down
+    Shutdown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Supervision {
+    pub restart: RestartPolicy,
+    pub escalation: Escalation,
+}
+
+impl Supervision {
+    /// Default supervision of each kind of component
+    ///
+    /// Strategies, state engines and executors are restarted a few times and shut the bot down
+    /// if they keep failing, as trading without them is unsafe. Collectors reconnect under their
+    /// own policy, so a collector giving up only loses its venue. Recorders are restarted but
+    /// never stop trading.
+    pub fn default_for(kind: ComponentKind) -> Self {
+        match kind {
+            ComponentKind::StateEngine | ComponentKind::Strategy | ComponentKind::Executor => {
+                Self {
+                    restart: RestartPolicy::OnFailure { max_restarts: 3 },
+                    escalation: Escalation::Shutdown,
+                }
+            }
+            ComponentKind::Collector => Self {
+                restart: RestartPolicy::Never,
+                escalation: Escalation::Isolate,
+            },
+            ComponentKind::Recorder => Self {
+                restart: RestartPolicy::OnFailure { max_restarts: 3 },
+                escalation: Escalation::Isolate,
+            },
+        }
+    }
+}
+
+/// One run of a component, from start until it exits
+#[async_trait::async_trait]
+pub(crate) trait Supervised: Send {
+    /// Runs the component, returning the error it failed with, if any
+    ///
+    /// Called again on restart, so any state kept across runs must be left consistent.
+    async fn run(&mut self, id: &ComponentId, monitor: &Monitor) -> Result<(), String>;
+}
+
+/// Runs a component under its supervision policy until it stops for good
+///
+/// Panics are caught and treated as failures. A component returning `Ok` completed its work,
+/// e.g. an executor no strategy routes to, and is only restarted under [`RestartPolicy::OnExit`].
+/// Restarts are delayed by `backoff` on wall time, like collector reconnects, so they proceed
+/// while a simulated clock stands still.
+///
+/// The component is not restarted once `stop` is cancelled, which also interrupts any pending
+/// restart. `stop` is the bot `shutdown` or a child of it, for components stopping ahead of the
+/// rest of the bot. Failures escalated to the bot cancel `shutdown`.
+pub(crate) async fn supervise(
+    mut component: Box<dyn Supervised>,
+    id: ComponentId,
+    monitor: Monitor,
+    supervision: Supervision,
+    backoff: ReconnectPolicy,
+    stop: CancellationToken,
+    shutdown: CancellationToken,
+) {
+    let mut restarts = 0;
+
+    loop {
+        let outcome = AssertUnwindSafe(component.run(&id, &monitor))
+            .catch_unwind()
+            .await
+            .unwrap_or_else(|panic| Err(format!("Panicked: {}", panic_message(panic.as_ref()))));
+
+        if stop.is_cancelled() {
+            if let Err(error) = outcome {
+                tracing::warn!("{} failed during shutdown: {}", id, error);
+            }
+            monitor.set(&id, ComponentStatus::Stopped);
+            return;
+        }
+
+        let error = match (outcome, supervision.restart) {
+            (Ok(()), RestartPolicy::OnExit) => "Exited before shutdown".to_string(),
+            (Ok(()), _) => {
+                monitor.set(&id, ComponentStatus::Stopped);
+                return;
+            }
+            (Err(error), _) => error,
+        };
+
+        let restart = match supervision.restart {
+            RestartPolicy::Never => false,
+            RestartPolicy::OnFailure { max_restarts } => restarts < max_restarts,
+            RestartPolicy::Always | RestartPolicy::OnExit => true,
+        };
+        if !restart {
+            monitor.fail(&id, error);
+            if supervision.escalation == Escalation::Shutdown {
+                tracing::error!("{} failed for good, shutting the bot down", id);
+                shutdown.cancel();
+            }
+            return;
+        }
+
+        let delay = backoff.delay(restarts);
+        restarts += 1;
+        tracing::warn!(
+            "{} failed: {}, restarting in {:?} (restart {})",
+            id,
+            error,
+            delay,
+            restarts
+        );
+        BotMetrics::record_error(&id.name);
+        BotMetrics::record_restart(&id.name);
+        monitor.set(&id, ComponentStatus::Restarting { restarts, error });
+
+        tokio::select! {
+            biased;
+            _ = stop.cancelled() => {
+                monitor.set(&id, ComponentStatus::Stopped);
+                return;
+            }
+            _ = tokio::time::sleep(delay) => {}
+        }
+    }
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic")
+}
+
+/// Status board shared with the component tasks
+#[derive(Debug, Clone)]
+pub(crate) struct Monitor {
+    statuses: Arc<Mutex<BTreeMap<ComponentId, ComponentStatus>>>,
+    failures: mpsc::UnboundedSender<ComponentFailure>,
+}
+
+impl Monitor {
+    pub(crate) fn new() -> (Self, mpsc::UnboundedReceiver<ComponentFailure>) {
+        let (failures, failures_rx) = mpsc::unbounded_channel();
+        let monitor = Self {
+            statuses: Arc::new(Mutex::new(BTreeMap::new())),
+            failures,
+        };
+        (monitor, failures_rx)
+    }
+
+    /// Registers a component, suffixing its name if another component of the kind already uses it
+    pub(crate) fn register(&self, kind: ComponentKind, name: &str) -> ComponentId {
+        let mut statuses = self
+            .statuses
+            .lock()
+            .expect("Component status lock poisoned");
+
+        let mut id = ComponentId {
+            kind,
+            name: name.to_string(),
+        };
+        let mut index = 1;
+        while statuses.contains_key(&id) {
+            index += 1;
+            id.name = format!("{}#{}", name, index);
+        }
+
+        statuses.insert(id.clone(), ComponentStatus::Starting);
+        id
+    }
+
+    pub(crate) fn statuses(&self) -> BTreeMap<ComponentId, ComponentStatus> {
+        self.statuses
+            .lock()
+            .expect("Component status lock poisoned")
+            .clone()
+    }
+
+    pub(crate) fn set(&self, id: &ComponentId, status: ComponentStatus) {
+        self.statuses
+            .lock()
+            .expect("Component status lock poisoned")
+            .insert(id.clone(), status);
+    }
+
+    /// Marks a component as failed for good and reports it to the handle
+    pub(crate) fn fail(&self, id: &ComponentId, error: String) {
+        tracing::error!("{} failed: {}", id, error);
+        BotMetrics::record_error(&id.name);
+        self.set(id, ComponentStatus::Failed(error.clone()));
+
+        // The handle owns the receiver, it is only gone once the bot is dropped
+        let _ = self.failures.send(ComponentFailure {
+            component: id.clone(),
+            error,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::VecDeque,
+        sync::atomic::{AtomicU32, Ordering},
+        time::Duration,
+    };
+
+    use super::*;
+
+    enum Outcome {
+        Done,
+        Fail,
+        Panic,
+    }
+
+    /// Component running through a script of outcomes, then done
+    struct Script {
+        outcomes: VecDeque<Outcome>,
+        runs: Arc<AtomicU32>,
+    }
+
+    #[async_trait::async_trait]
+    impl Supervised for Script {
+        async fn run(&mut self, _id: &ComponentId, _monitor: &Monitor) -> Result<(), String> {
+            self.runs.fetch_add(1, Ordering::SeqCst);
+            match self.outcomes.pop_front() {
+                Some(Outcome::Fail) => Err("failed".to_string()),
+                Some(Outcome::Panic) => panic!("boom"),
+                Some(Outcome::Done) | None => Ok(()),
+            }
+        }
+    }
+
+    struct Run {
+        runs: u32,
+        status: ComponentStatus,
+        failure: Option<ComponentFailure>,
+        shutdown: bool,
+    }
+
+    async fn supervised(
+        outcomes: impl IntoIterator<Item = Outcome>,
+        restart: RestartPolicy,
+        escalation: Escalation,
+    ) -> Run {
+        let runs = Arc::new(AtomicU32::new(0));
+        let component = Script {
+            outcomes: outcomes.into_iter().collect(),
+            runs: runs.clone(),
+        };
+        let (monitor, mut failures) = Monitor::new();
+        let id = monitor.register(ComponentKind::StateEngine, "script");
+        let shutdown = CancellationToken::new();
+        let backoff = ReconnectPolicy {
+            initial_delay_ms: 1,
+            jitter: 0.0,
+            ..Default::default()
+        };
+
+        let supervision = Supervision {
+            restart,
+            escalation,
+        };
+        let task = supervise(
+            Box::new(component),
+            id.clone(),
+            monitor.clone(),
+            supervision,
+            backoff,
+            shutdown.clone(),
+            shutdown.clone(),
+        );
+        tokio::time::timeout(Duration::from_secs(5), task)
+            .await
+            .expect("component still supervised");
+
+        Run {
+            runs: runs.load(Ordering::SeqCst),
+            status: monitor.statuses()[&id].clone(),
+            failure: failures.try_recv().ok(),
+            shutdown: shutdown.is_cancelled(),
+        }
+    }
+
+    #[tokio::test]
+    async fn panics_are_restarted_like_failures() {
+        let run = supervised(
+            [Outcome::Panic, Outcome::Fail, Outcome::Done],
+            RestartPolicy::OnFailure { max_restarts: 3 },
+            Escalation::Shutdown,
+        )
+        .await;
+
+        assert_eq!(run.runs, 3);
+        assert_eq!(run.status, ComponentStatus::Stopped);
+        assert!(run.failure.is_none());
+        assert!(!run.shutdown);
+    }
+
+    #[tokio::test]
+    async fn restarts_stop_at_the_limit() {
+        let run = supervised(
+            std::iter::repeat_with(|| Outcome::Panic).take(10),
+            RestartPolicy::OnFailure { max_restarts: 2 },
+            Escalation::Isolate,
+        )
+        .await;
+
+        assert_eq!(run.runs, 3);
+        assert_eq!(
+            run.status,
+            ComponentStatus::Failed("Panicked: boom".to_string())
+        );
+        assert_eq!(run.failure.unwrap().error, "Panicked: boom");
+        assert!(!run.shutdown);
+    }
+
+    #[tokio::test]
+    async fn escalated_failures_shut_the_bot_down() {
+        let run = supervised([Outcome::Fail], RestartPolicy::Never, Escalation::Shutdown).await;
+
+        assert_eq!(run.runs, 1);
+        assert!(run.failure.is_some());
+        assert!(run.shutdown);
+    }
+
+    #[tokio::test]
+    async fn clean_exits_are_not_restarted_after_failures() {
+        let run = supervised(
+            [Outcome::Fail, Outcome::Fail, Outcome::Done],
+            RestartPolicy::Always,
+            Escalation::Shutdown,
+        )
+        .await;
+
+        assert_eq!(run.runs, 3);
+        assert_eq!(run.status, ComponentStatus::Stopped);
+        assert!(!run.shutdown);
+    }
+
+    #[tokio::test]
+    async fn clean_exits_are_restarted_on_exit() {
+        let runs = Arc::new(AtomicU32::new(0));
+        let component = Script {
+            outcomes: VecDeque::new(),
+            runs: runs.clone(),
+        };
+        let (monitor, _failures) = Monitor::new();
+        let id = monitor.register(ComponentKind::Executor, "script");
+        let shutdown = CancellationToken::new();
+        let supervision = Supervision {
+            restart: RestartPolicy::OnExit,
+            escalation: Escalation::Shutdown,
+        };
+        let backoff = ReconnectPolicy {
+            initial_delay_ms: 1,
+            max_delay_ms: 1,
+            jitter: 0.0,
+            ..Default::default()
+        };
+        let task = tokio::spawn(supervise(
+            Box::new(component),
+            id.clone(),
+            monitor.clone(),
+            supervision,
+            backoff,
+            shutdown.clone(),
+            shutdown.clone(),
+        ));
+
+        while runs.load(Ordering::SeqCst) < 3 {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        shutdown.cancel();
+        task.await.unwrap();
+        assert_eq!(monitor.statuses()[&id], ComponentStatus::Stopped);
+    }
+
+    #[tokio::test]
+    async fn stopped_components_are_not_restarted() {
+        let runs = Arc::new(AtomicU32::new(0));
+        let component = Script {
+            outcomes: VecDeque::new(),
+            runs: runs.clone(),
+        };
+        let (monitor, _failures) = Monitor::new();
+        let id = monitor.register(ComponentKind::Strategy, "script");
+        let shutdown = CancellationToken::new();
+        let stop = shutdown.child_token();
+        stop.cancel();
+
+        let supervision = Supervision {
+            restart: RestartPolicy::OnExit,
+            escalation: Escalation::Shutdown,
+        };
+        supervise(
+            Box::new(component),
+            id.clone(),
+            monitor.clone(),
+            supervision,
+            ReconnectPolicy::default(),
+            stop,
+            shutdown.clone(),
+        )
+        .await;
+
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert_eq!(monitor.statuses()[&id], ComponentStatus::Stopped);
+        assert!(!shutdown.is_cancelled());
+    }
+}
@@ -0,0 +1,75 @@
+//! Fixtures shared by the unit tests
+
+use std::path::PathBuf;
+
+use crate::models::{
+    event::EventSource,
+    instrument::{Instrument, InstrumentKind},
+    order::{ExecutionKind, ExecutionReport, Fill, Liquidity, Side},
+    trade::Trade,
+    traits::{OneShot, StateEngine},
+};
+
+pub fn instrument() -> Instrument {
+    Instrument::new("BTC", "USDT", InstrumentKind::Spot, "BTCUSDT")
+}
+
+pub fn trade(source: EventSource, price: f64, size: f64, timestamp: u64) -> Trade {
+    Trade {
+        source,
+        instrument: instrument(),
+        price,
+        size,
+        timestamp,
+    }
+}
+
+/// Taker fill of the order `client_id` on Binance
+pub fn fill(client_id: &str, side: Side, price: f64, quantity: f64, fee: f64, at: u64) -> Fill {
+    Fill {
+        client_id: client_id.to_string(),
+        source: EventSource::Binance,
+        instrument: instrument(),
+        side,
+        price,
+        quantity,
+        fee,
+        liquidity: Liquidity::Taker,
+        timestamp: at,
+        strategy_id: None,
+    }
+}
+
+pub fn fill_report(fill: Fill) -> ExecutionReport {
+    ExecutionReport {
+        client_id: fill.client_id.clone(),
+        source: fill.source.clone(),
+        instrument: fill.instrument.clone(),
+        timestamp: fill.timestamp,
+        kind: ExecutionKind::Fill(fill),
+    }
+}
+
+/// Output of a state engine, requested the way strategies do
+pub fn request<E, D>(engine: &dyn StateEngine<E, D>) -> D {
+    let (request, mut response) = OneShot::new();
+    engine.process_request(request).unwrap();
+    response.try_recv().unwrap()
+}
+
+/// Empty directory for the files of a test, unique to the test and the process
+pub fn temp_dir(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("shiden-{}-{}", test, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+pub fn assert_close(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < 1e-9,
+        "expected {}, got {}",
+        expected,
+        actual
+    );
+}